@@ -13,7 +13,9 @@ use bevy::{
 };
 
 use bevy_eulerian_fluid::{
-    definition::{FluidSettings, SimulationUniform, VelocityTextures},
+    definition::{
+        FluidInitialized, FluidSettings, ResetFluid, SimulationUniform, VelocityTextures,
+    },
     geometry::{self},
     material::VelocityMaterial,
     FluidPlugin,
@@ -59,7 +61,7 @@ fn main() {
                 ..default()
             }),
     )
-    .add_plugins(FluidPlugin)
+    .add_plugins(FluidPlugin::default())
     .add_plugins(FpsCounterPlugin)
     .add_plugins(GameUiPlugin)
     .add_systems(Startup, setup_scene)
@@ -86,23 +88,27 @@ fn setup_scene(mut commands: Commands) {
         ))
         .insert(Name::new("Light"));
 
-    commands.spawn(FluidSettings {
-        dx: 1.0f32,
-        dt: 0.5f32,
-        rho: 1.293f32,
-        gravity: Vec2::ZERO,
-        size: (512, 512),
-        initial_fluid_level: 1.0f32,
-    });
+    commands.spawn(
+        FluidSettings::builder()
+            .air()
+            .size((512, 512))
+            .build()
+            .unwrap(),
+    );
 }
 
 fn on_fluid_setup(
     mut commands: Commands,
-    query: Query<&VelocityTextures, Added<VelocityTextures>>,
+    mut events: EventReader<FluidInitialized>,
+    query: Query<&VelocityTextures, Without<Mesh3d>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<VelocityMaterial>>,
 ) {
-    for velocity_texture in &query {
+    for event in events.read() {
+        // FluidInitialized is sent again on reset, while the visualization is already attached.
+        let Ok(velocity_texture) = query.get(event.entity) else {
+            continue;
+        };
         let mesh =
             meshes.add(Mesh::from(Plane3d::default()).translated_by(Vec3::new(0.0, 1.0, 1.0)));
         let material = materials.add(VelocityMaterial {
@@ -111,7 +117,9 @@ fn on_fluid_setup(
             u: velocity_texture.u0.clone(),
             v: velocity_texture.v0.clone(),
         });
-        commands.spawn((Mesh3d(mesh), MeshMaterial3d(material)));
+        commands
+            .entity(event.entity)
+            .insert((Mesh3d(mesh), MeshMaterial3d(material)));
     }
 }
 
@@ -139,12 +147,17 @@ fn button_update(
     mut commands: Commands,
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>, With<ResetButton>)>,
     object_query: Query<Entity, With<geometry::Circle>>,
+    fluid_query: Query<Entity, With<FluidSettings>>,
+    mut reset_events: EventWriter<ResetFluid>,
 ) {
     for interaction in &interaction_query {
         if *interaction == Interaction::Pressed {
             for entity in object_query.iter() {
                 commands.entity(entity).despawn();
             }
+            for entity in fluid_query.iter() {
+                reset_events.send(ResetFluid { entity });
+            }
         }
     }
 }
@@ -10,7 +10,7 @@ use bevy::{
 };
 
 use bevy_eulerian_fluid::{
-    definition::{FluidSettings, VelocityTextures},
+    definition::{FluidInitialized, FluidSettings, VelocityTextures},
     material::VelocityMaterial,
     FluidPlugin,
 };
@@ -52,7 +52,7 @@ fn main() {
                 ..default()
             }),
     )
-    .add_plugins(FluidPlugin)
+    .add_plugins(FluidPlugin::default())
     .add_plugins(FpsCounterPlugin)
     .add_systems(Startup, setup_scene)
     .add_systems(Update, on_fluid_setup)
@@ -65,25 +65,27 @@ fn setup_scene(mut commands: Commands) {
     commands.spawn(Camera2d);
 
     commands.spawn((
-        FluidSettings {
-            dx: 1.0f32,
-            dt: 0.5f32,
-            rho: 1.293f32, // air
-            gravity: Vec2::ZERO,
-            size: (256, 256),
-            initial_fluid_level: 1.0f32,
-        },
+        FluidSettings::builder()
+            .air()
+            .size((256, 256))
+            .build()
+            .unwrap(),
         Transform::default().with_scale(Vec3::splat(256.0)),
     ));
 }
 
 fn on_fluid_setup(
     mut commands: Commands,
-    query: Query<(Entity, &VelocityTextures), Added<VelocityTextures>>,
+    mut events: EventReader<FluidInitialized>,
+    query: Query<&VelocityTextures>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<VelocityMaterial>>,
 ) {
-    for (entity, velocity_texture) in &query {
+    for event in events.read() {
+        let entity = event.entity;
+        let Ok(velocity_texture) = query.get(entity) else {
+            continue;
+        };
         let mesh = meshes.add(Rectangle::default());
         let material = materials.add(VelocityMaterial {
             u_range: Vec2::new(-10.0, 10.0),
@@ -9,7 +9,7 @@ use bevy::{
     },
 };
 use bevy_eulerian_fluid::{
-    definition::{FluidSettings, VelocityTextures},
+    definition::{FluidInitialized, FluidSettings, VelocityTextures},
     material::VelocityMaterial,
     FluidPlugin,
 };
@@ -51,7 +51,7 @@ fn main() {
                     ..default()
                 }),
         )
-        .add_plugins(FluidPlugin)
+        .add_plugins(FluidPlugin::default())
         .add_plugins(FpsCounterPlugin)
         .add_systems(Startup, setup_scene)
         .add_systems(Update, (mouse_motion, on_fluid_setup))
@@ -71,14 +71,13 @@ fn setup_scene(mut commands: Commands) {
                 0.0,
             );
             commands
-                .spawn(FluidSettings {
-                    dx: 1.0f32,
-                    dt: 0.5f32,
-                    rho: 1.293f32,
-                    gravity: Vec2::ZERO,
-                    size: (size, size),
-                    initial_fluid_level: 1.0f32,
-                })
+                .spawn(
+                    FluidSettings::builder()
+                        .air()
+                        .size((size, size))
+                        .build()
+                        .unwrap(),
+                )
                 .insert(
                     Transform::default()
                         .with_scale(Vec3::splat(size as f32))
@@ -90,11 +89,15 @@ fn setup_scene(mut commands: Commands) {
 
 fn on_fluid_setup(
     mut commands: Commands,
-    query: Query<(&VelocityTextures, &Transform), Added<VelocityTextures>>,
+    mut events: EventReader<FluidInitialized>,
+    query: Query<(&VelocityTextures, &Transform)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<VelocityMaterial>>,
 ) {
-    for (velocity_texture, transform) in &query {
+    for event in events.read() {
+        let Ok((velocity_texture, transform)) = query.get(event.entity) else {
+            continue;
+        };
         // spwan plane to visualize advection
         let mesh = meshes.add(Rectangle::default());
         let material = materials.add(VelocityMaterial {
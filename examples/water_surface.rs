@@ -12,7 +12,7 @@ use bevy::{
 };
 
 use bevy_eulerian_fluid::{
-    definition::{FluidSettings, LevelsetTextures, VelocityTextures},
+    definition::{FluidInitialized, FluidSettings, LevelsetTextures, VelocityTextures},
     material::VelocityMaterial,
     FluidPlugin,
 };
@@ -55,7 +55,7 @@ fn main() {
                 ..default()
             }),
     )
-    .add_plugins(FluidPlugin)
+    .add_plugins(FluidPlugin::default())
     .add_plugins(FpsCounterPlugin)
     .add_plugins(Material2dPlugin::<CustomMaterial>::default())
     .add_systems(Startup, setup_scene)
@@ -68,24 +68,30 @@ fn main() {
 fn setup_scene(mut commands: Commands) {
     commands.spawn(Camera2d);
 
-    commands.spawn(FluidSettings {
-        dx: 1.0f32,
-        dt: 0.5f32,
-        rho: 997f32, // water
-        gravity: Vec2::Y,
-        size: SIZE,
-        initial_fluid_level: 0.9,
-    });
+    commands.spawn(
+        FluidSettings::builder()
+            .water()
+            .gravity(Vec2::Y)
+            .size(SIZE)
+            .initial_fluid_level(0.9)
+            .build()
+            .unwrap(),
+    );
 }
 
 fn on_fluid_setup(
     mut commands: Commands,
-    query: Query<(Entity, &LevelsetTextures, &VelocityTextures), Added<LevelsetTextures>>,
+    mut events: EventReader<FluidInitialized>,
+    query: Query<(&LevelsetTextures, &VelocityTextures)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<CustomMaterial>>,
     mut velocity_materials: ResMut<Assets<VelocityMaterial>>,
 ) {
-    for (entity, levelset_textures, velocity_textures) in &query {
+    for event in events.read() {
+        let entity = event.entity;
+        let Ok((levelset_textures, velocity_textures)) = query.get(entity) else {
+            continue;
+        };
         let mesh = meshes.add(Rectangle::default());
         let material = materials.add(CustomMaterial {
             levelset: levelset_textures.levelset.clone(),
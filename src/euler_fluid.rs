@@ -17,32 +17,83 @@ use bevy::{
         extract_resource::ExtractResourcePlugin,
         graph::CameraDriverLabel,
         render_graph::RenderGraph,
-        Render, RenderApp, RenderSet,
+        ExtractSchedule, Render, RenderApp, RenderSet,
     },
 };
 use definition::{
-    CircleObstacle, DivergenceTextures, JumpFloodingSeedsTextures, LocalForces, Obstacles,
-    PressureTextures, SimulationUniform, VelocityTextures,
+    CircleObstacle, DivergenceTextures, FluidConfig, FluidError, FluidInitialized,
+    JumpFloodingSeedsTextures, LocalForces, Obstacles, PressureTextures, ResetFluid,
+    SimulationControl, SimulationUniform, VelocityTextures,
 };
 use fluid_bind_group::FluidPipelines;
 use geometry::Velocity;
 
-use render_node::{EulerFluidNode, FluidLabel};
+use render_node::{
+    EulerFluidNode, FluidErrorQueue, FluidInitializedQueue, FluidLabel, FluidReadbackQueue,
+};
 
-use setup_components::watch_fluid_component;
+use setup_components::{
+    count_fixed_steps, receive_fluid_readback, send_fluid_errors, send_fluid_initialized,
+    teardown_fluid, update_pressure_iterations, update_simulation_steps, watch_fluid_component,
+    watch_fluid_settings_change, FluidTexturePool, PendingFixedSteps, PressureIterations,
+    ResolvedSolverSettings, SimulationSteps,
+};
 
 const FLUID_UNIFORM_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x8B9323522322463BA8CF530771C532EF);
 
 const COORDINATE_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x9F8E2E5B1E5F40C096C31175C285BF11);
+const CUT_CELL_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0xC26A0F8E4D5B47A39E1B7F0D6A84C352);
+const SOLID_VELOCITY_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5D2B8E71A04C4F96B3E7C1A9F2086D4B);
+const GHOST_FLUID_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x3B7D92E4C8A1406FA5E2D1C9074B6F38);
+const INTERPOLATION_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x6E1C4A9B27D84F0E93B5A8D2C71F0E46);
+
+/// System sets of [`FluidPlugin`] to order systems against the simulation.
+/// The simulation itself runs on the render graph node labeled [`FluidLabel`].
+/// # Examples
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::{definition::LocalForces, FluidSet};
+///
+/// fn apply_forces(mut query: Query<&mut LocalForces>) {
+///     // Write forces and positions to be applied on this frame.
+/// }
+///
+/// fn build(app: &mut App) {
+///     app.add_systems(Update, apply_forces.before(FluidSet::Prepare));
+/// }
+/// ```
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FluidSet {
+    /// In [`Update`] on the main world.
+    /// Reflects [`FluidSettings`], [`FluidSolverSettings`](definition::FluidSolverSettings) and the obstacles to the simulation.
+    /// Systems writing [`LocalForces`], [`Obstacles`] or the geometries should run before this set.
+    Prepare,
+    /// In [`Render`] on the render world, as a part of [`RenderSet::PrepareResources`].
+    /// Prepares the buffers used by the simulation.
+    PrepareResources,
+    /// In [`Render`] on the render world, as a part of [`RenderSet::PrepareBindGroups`].
+    /// Prepares the bind groups used by the simulation.
+    PrepareBindGroups,
+}
 
-pub struct FluidPlugin;
+/// Plugin for fluid simulation. See [`FluidConfig`] for the configurations.
+#[derive(Default)]
+pub struct FluidPlugin {
+    pub config: FluidConfig,
+}
 
 impl Plugin for FluidPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(ExtractResourcePlugin::<Obstacles>::default())
+            .add_plugins(ExtractResourcePlugin::<FluidConfig>::default())
             .add_plugins(ExtractComponentPlugin::<FluidSettings>::default())
+            .add_plugins(ExtractComponentPlugin::<ResolvedSolverSettings>::default())
             .add_plugins(ExtractComponentPlugin::<FluidBindGroups>::default())
             .add_plugins(ExtractComponentPlugin::<VelocityTextures>::default())
             .add_plugins(ExtractComponentPlugin::<PressureTextures>::default())
@@ -51,30 +102,122 @@ impl Plugin for FluidPlugin {
             .add_plugins(ExtractComponentPlugin::<JumpFloodingSeedsTextures>::default())
             .add_plugins(ExtractComponentPlugin::<LocalForces>::default())
             .add_plugins(ExtractComponentPlugin::<SimulationUniform>::default())
+            .add_plugins(ExtractComponentPlugin::<SimulationSteps>::default())
+            .add_plugins(ExtractComponentPlugin::<PressureIterations>::default())
             .add_plugins(UniformComponentPlugin::<SimulationUniform>::default())
             .add_plugins(FluidMaterialPlugin)
-            .add_systems(Update, update_geometry)
-            .add_systems(Update, watch_fluid_component);
+            .add_event::<ResetFluid>()
+            .add_event::<FluidInitialized>()
+            .add_event::<FluidError>()
+            .add_systems(Update, update_geometry.in_set(FluidSet::Prepare))
+            .add_systems(Update, watch_fluid_component.in_set(FluidSet::Prepare))
+            .add_systems(
+                Update,
+                watch_fluid_settings_change.in_set(FluidSet::Prepare),
+            )
+            .add_systems(
+                Update,
+                update_simulation_steps
+                    .after(watch_fluid_settings_change)
+                    .in_set(FluidSet::Prepare),
+            )
+            .add_systems(
+                Update,
+                update_pressure_iterations
+                    .after(watch_fluid_settings_change)
+                    .in_set(FluidSet::Prepare),
+            )
+            .add_systems(FixedUpdate, count_fixed_steps)
+            .add_systems(
+                PreUpdate,
+                (
+                    send_fluid_initialized,
+                    send_fluid_errors,
+                    receive_fluid_readback,
+                ),
+            )
+            .add_observer(teardown_fluid);
+
+        let initialized_queue = FluidInitializedQueue::default();
+        let error_queue = FluidErrorQueue::default();
+        let max_velocity_queue = FluidReadbackQueue::default();
+        app.insert_resource(initialized_queue.clone())
+            .insert_resource(error_queue.clone())
+            .insert_resource(max_velocity_queue.clone())
+            .insert_resource(self.config.clone())
+            .init_resource::<SimulationControl>()
+            .init_resource::<PendingFixedSteps>();
 
         let render_app = app.sub_app_mut(RenderApp);
         render_app
+            .insert_resource(self.config.clone())
+            .insert_resource(initialized_queue)
+            .insert_resource(error_queue)
+            .insert_resource(max_velocity_queue)
+            .configure_sets(
+                Render,
+                (
+                    FluidSet::PrepareResources.in_set(RenderSet::PrepareResources),
+                    FluidSet::PrepareBindGroups.in_set(RenderSet::PrepareBindGroups),
+                ),
+            )
+            .add_systems(ExtractSchedule, render_node::extract_reset_fluid)
+            .add_systems(ExtractSchedule, render_node::extract_removed_fluid)
             .add_systems(
                 Render,
                 fluid_bind_group::prepare_resource_recompute_levelset
-                    .in_set(RenderSet::PrepareResources),
+                    .in_set(FluidSet::PrepareResources),
             )
             .add_systems(
                 Render,
-                fluid_bind_group::prepare_fluid_bind_groups.in_set(RenderSet::PrepareBindGroups),
+                fluid_bind_group::prepare_fluid_bind_groups.in_set(FluidSet::PrepareBindGroups),
             )
             .add_systems(
                 Render,
                 fluid_bind_group::prepare_fluid_bind_group_for_resources
-                    .in_set(RenderSet::PrepareBindGroups),
+                    .in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_multigrid_bind_groups.in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_pcg_bind_groups.in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_mac_cormack_bind_groups
+                    .in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_vorticity_bind_groups.in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_viscosity_bind_groups.in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_extrapolation_bind_groups
+                    .in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::prepare_fluid_readback.in_set(FluidSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                fluid_bind_group::cleanup_resample_bind_groups.in_set(RenderSet::Cleanup),
+            )
+            .add_systems(
+                Render,
+                render_node::readback_fluid.in_set(RenderSet::Cleanup),
             );
 
-        let mut world = render_app.world_mut();
-        let euler_fluid_node = EulerFluidNode::new(&mut world);
+        let world = render_app.world_mut();
+        let euler_fluid_node = EulerFluidNode::new(world);
         let mut render_graph = world.resource_mut::<RenderGraph>();
         render_graph.add_node(FluidLabel, euler_fluid_node);
         render_graph.add_node_edge(FluidLabel, CameraDriverLabel);
@@ -93,6 +236,34 @@ impl Plugin for FluidPlugin {
             Shader::from_wgsl
         );
 
+        load_internal_asset!(
+            app,
+            CUT_CELL_SHADER_HANDLE,
+            "euler_fluid/shaders/cut_cell.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            SOLID_VELOCITY_SHADER_HANDLE,
+            "euler_fluid/shaders/solid_velocity.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            INTERPOLATION_SHADER_HANDLE,
+            "euler_fluid/shaders/interpolation.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            GHOST_FLUID_SHADER_HANDLE,
+            "euler_fluid/shaders/ghost_fluid.wgsl",
+            Shader::from_wgsl
+        );
+
         load_internal_asset!(
             app,
             fluid_bind_group::INITIALIZE_GRID_CENTER_SHADER_HANDLE,
@@ -107,6 +278,13 @@ impl Plugin for FluidPlugin {
             Shader::from_wgsl
         );
 
+        load_internal_asset!(
+            app,
+            fluid_bind_group::CLEAR_GRID_CENTER_SHADER_HANDLE,
+            "euler_fluid/shaders/clear_grid_center.wgsl",
+            Shader::from_wgsl
+        );
+
         load_internal_asset!(
             app,
             fluid_bind_group::UPDATE_GRID_LABEL_SHADER_HANDLE,
@@ -142,6 +320,27 @@ impl Plugin for FluidPlugin {
             Shader::from_wgsl
         );
 
+        load_internal_asset!(
+            app,
+            fluid_bind_group::RED_BLACK_SOR_SHADER_HANDLE,
+            "euler_fluid/shaders/red_black_sor.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::MULTIGRID_SHADER_HANDLE,
+            "euler_fluid/shaders/multigrid.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::PCG_SHADER_HANDLE,
+            "euler_fluid/shaders/pcg.wgsl",
+            Shader::from_wgsl
+        );
+
         load_internal_asset!(
             app,
             fluid_bind_group::SOLVE_VELOCITY_SHADER_HANDLE,
@@ -149,6 +348,13 @@ impl Plugin for FluidPlugin {
             Shader::from_wgsl
         );
 
+        load_internal_asset!(
+            app,
+            fluid_bind_group::EXTRAPOLATE_VELOCITY_SHADER_HANDLE,
+            "euler_fluid/shaders/extrapolate_velocity.wgsl",
+            Shader::from_wgsl
+        );
+
         load_internal_asset!(
             app,
             fluid_bind_group::RECOMPUTE_LEVELSET_INITIALIZE_SHADER_HANDLE,
@@ -176,10 +382,53 @@ impl Plugin for FluidPlugin {
             "euler_fluid/shaders/advect_levelset.wgsl",
             Shader::from_wgsl
         );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::MAC_CORMACK_SHADER_HANDLE,
+            "euler_fluid/shaders/mac_cormack.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::VORTICITY_SHADER_HANDLE,
+            "euler_fluid/shaders/vorticity.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::VISCOSITY_SHADER_HANDLE,
+            "euler_fluid/shaders/viscosity.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::RESAMPLE_SHADER_HANDLE,
+            "euler_fluid/shaders/resample.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::MAX_VELOCITY_SHADER_HANDLE,
+            "euler_fluid/shaders/max_velocity.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            fluid_bind_group::RESIDUAL_SHADER_HANDLE,
+            "euler_fluid/shaders/residual.wgsl",
+            Shader::from_wgsl
+        );
     }
 
     fn finish(&self, app: &mut App) {
-        app.init_resource::<Obstacles>();
+        app.init_resource::<Obstacles>()
+            .init_resource::<FluidTexturePool>();
 
         let render_app = app.sub_app_mut(RenderApp);
         render_app.init_resource::<FluidPipelines>();
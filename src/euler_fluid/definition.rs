@@ -14,9 +14,29 @@ use bevy::{
 /// * `size`: The size of 2D simulation domain in pixels. The size is recommended to be same between each dimension and to be multiple of 64 pixels.
 /// * `dx`: The size of a pixel in unit of [m/pixel].
 /// * `dt`: The temporal resolution of the simulation in unit of [sec].
-/// * `rho`: The density of fluid in unit of [kg/m^3]. Currently, uniform density is supported only.
+///   The simulation advances by `dt` on every tick of [`Time<Fixed>`], independently of the frame rate.
+///   Set `dt` to the timestep of [`Time<Fixed>`] to run the simulation in real time. A warning is logged when they differ.
+/// * `rho`: The density of fluid in unit of [kg/m^3].
+/// * `air_rho`: Enables two-phase flow with the specified density of the air in unit of [kg/m^3].
+///   The air cells, where the levelset is positive, are solved together with the fluid with their own inertia,
+///   so that air pockets and bubbles rise through the fluid. `None` treats the air as empty with zero pressure.
+///   All the pressure solvers support it, though [`PressureSolver::Multigrid`] corrects only the fluid cells on its coarse levels
+///   and converges slower in the air.
 /// * `initial_fluid_level`: Initialize fluid level with specified value. the value is valid between 0.0 - 1.0. 0.0 indicates empty and 1.0 indicates the simulation domain is filled with fluid.
 /// * `gravity`: Uniform force enforced uniformly to the simulation domain in unit of [m/s^2].
+/// * `surface_tension`: Surface tension coefficient of the interface between fluid and air in unit of [N/m].
+///   It is applied as a force along the curvature of the levelset near the interface, which rounds up small blobs into droplets.
+///   0.0 disables it. The surface tension is explicit, so `dt` must be below about `sqrt(rho * dx^3 / (2 * pi * surface_tension))`.
+/// * `viscosity`: Kinematic viscosity of fluid in unit of [m^2/s]. 0.0 makes the fluid inviscid and skips the viscous diffusion.
+///   See [`ViscositySolver`] to choose how the diffusion is solved.
+///
+/// Removing the settings or despawning the entity tears down the simulation.
+/// The textures are returned to [`FluidTexturePool`](crate::setup_components::FluidTexturePool) to be reused by fluids spawned later,
+/// once every handle to them, such as the ones held by materials for visualization, has been dropped.
+///
+/// Settings can be changed while the simulation is running. Scalar parameters are reflected to [`SimulationUniform`].
+/// Changing `size` reallocates all the textures, so handles of [`VelocityTextures`] and so on are replaced.
+/// The current velocity and levelset are resampled into the new grid.
 ///
 /// # Examples
 /// ```rust
@@ -37,6 +57,9 @@ use bevy::{
 ///         gravity: Vec2::ZERO,
 ///         size: (512, 512),
 ///         initial_fluid_level: 1.0f32,
+///         viscosity: 0.0f32,
+///         air_rho: None,
+///         surface_tension: 0.0f32,
 ///     });
 /// }
 ///
@@ -64,7 +87,7 @@ use bevy::{
 ///     }
 /// }
 /// ```
-#[derive(Component, Clone, ExtractComponent)]
+#[derive(Component, Clone, Debug, ExtractComponent)]
 pub struct FluidSettings {
     pub dx: f32,
     pub dt: f32,
@@ -72,6 +95,618 @@ pub struct FluidSettings {
     pub gravity: Vec2,
     pub size: (u32, u32),
     pub initial_fluid_level: f32,
+    pub viscosity: f32,
+    pub air_rho: Option<f32>,
+    pub surface_tension: f32,
+}
+
+impl FluidSettings {
+    /// Returns a builder to create validated settings. See [`FluidSettingsBuilder`].
+    pub fn builder() -> FluidSettingsBuilder {
+        FluidSettingsBuilder::default()
+    }
+
+    /// Checks that the settings can be simulated.
+    /// Settings spawned without [`FluidSettingsBuilder`] are checked when the simulation starts and a warning is logged if they are invalid.
+    pub fn validate(&self) -> Result<(), FluidSettingsError> {
+        let size = self.size;
+        if size.0 == 0 || size.1 == 0 || size.0 % 64 != 0 || size.1 % 64 != 0 {
+            return Err(FluidSettingsError::SizeNotMultipleOf64(size));
+        }
+        if size.0 != size.1 {
+            return Err(FluidSettingsError::NonSquareSize(size));
+        }
+        if !(self.dx > 0.0 && self.dx.is_finite()) {
+            return Err(FluidSettingsError::NonPositiveDx(self.dx));
+        }
+        if !(self.dt > 0.0 && self.dt.is_finite()) {
+            return Err(FluidSettingsError::NonPositiveDt(self.dt));
+        }
+        if !(self.rho > 0.0 && self.rho.is_finite()) {
+            return Err(FluidSettingsError::NonPositiveRho(self.rho));
+        }
+        if !self.gravity.is_finite() {
+            return Err(FluidSettingsError::NonFiniteGravity(self.gravity));
+        }
+        if !(0.0..=1.0).contains(&self.initial_fluid_level) {
+            return Err(FluidSettingsError::InitialFluidLevelOutOfRange(
+                self.initial_fluid_level,
+            ));
+        }
+        if !(self.viscosity >= 0.0 && self.viscosity.is_finite()) {
+            return Err(FluidSettingsError::NegativeViscosity(self.viscosity));
+        }
+        if !(self.surface_tension >= 0.0 && self.surface_tension.is_finite()) {
+            return Err(FluidSettingsError::NegativeSurfaceTension(
+                self.surface_tension,
+            ));
+        }
+        if let Some(air_rho) = self.air_rho {
+            if !(air_rho > 0.0 && air_rho.is_finite()) {
+                return Err(FluidSettingsError::NonPositiveAirRho(air_rho));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder of [`FluidSettings`] which validates the settings on [`FluidSettingsBuilder::build`].
+/// Unless specified, the settings are `size: (512, 512)`, `dx: 1.0`, `dt: 0.5`, no gravity, filled with water.
+/// # Examples
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::{FluidSettings, FluidSettingsError};
+///
+/// let settings = FluidSettings::builder()
+///     .water()
+///     .size((256, 256))
+///     .gravity(Vec2::Y)
+///     .initial_fluid_level(0.9)
+///     .build()
+///     .unwrap();
+/// assert_eq!(settings.rho, 997.0);
+///
+/// let error = FluidSettings::builder().dt(0.0).build().unwrap_err();
+/// assert_eq!(error, FluidSettingsError::NonPositiveDt(0.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FluidSettingsBuilder {
+    settings: FluidSettings,
+}
+
+impl Default for FluidSettingsBuilder {
+    fn default() -> Self {
+        Self {
+            settings: FluidSettings {
+                dx: 1.0,
+                dt: 0.5,
+                rho: Self::WATER_DENSITY,
+                gravity: Vec2::ZERO,
+                size: (512, 512),
+                initial_fluid_level: 1.0,
+                viscosity: 0.0,
+                air_rho: None,
+                surface_tension: 0.0,
+            },
+        }
+    }
+}
+
+impl FluidSettingsBuilder {
+    /// Density of water at 25 degrees Celsius in [kg/m^3].
+    pub const WATER_DENSITY: f32 = 997.0;
+    /// Density of air at 0 degrees Celsius and 1 atm in [kg/m^3].
+    pub const AIR_DENSITY: f32 = 1.293;
+    /// Typical density of vegetable oil in [kg/m^3].
+    pub const OIL_DENSITY: f32 = 920.0;
+    /// Surface tension of water against air at 25 degrees Celsius in [N/m].
+    pub const WATER_SURFACE_TENSION: f32 = 0.072;
+
+    /// Fills the fluid with water.
+    pub fn water(self) -> Self {
+        self.rho(Self::WATER_DENSITY)
+    }
+
+    /// Fills the fluid with air.
+    pub fn air(self) -> Self {
+        self.rho(Self::AIR_DENSITY)
+    }
+
+    /// Fills the fluid with oil.
+    pub fn oil(self) -> Self {
+        self.rho(Self::OIL_DENSITY)
+    }
+
+    pub fn size(mut self, size: (u32, u32)) -> Self {
+        self.settings.size = size;
+        self
+    }
+
+    pub fn dx(mut self, dx: f32) -> Self {
+        self.settings.dx = dx;
+        self
+    }
+
+    pub fn dt(mut self, dt: f32) -> Self {
+        self.settings.dt = dt;
+        self
+    }
+
+    pub fn rho(mut self, rho: f32) -> Self {
+        self.settings.rho = rho;
+        self
+    }
+
+    pub fn gravity(mut self, gravity: Vec2) -> Self {
+        self.settings.gravity = gravity;
+        self
+    }
+
+    pub fn initial_fluid_level(mut self, initial_fluid_level: f32) -> Self {
+        self.settings.initial_fluid_level = initial_fluid_level;
+        self
+    }
+
+    pub fn viscosity(mut self, viscosity: f32) -> Self {
+        self.settings.viscosity = viscosity;
+        self
+    }
+
+    pub fn surface_tension(mut self, surface_tension: f32) -> Self {
+        self.settings.surface_tension = surface_tension;
+        self
+    }
+
+    /// Enables two-phase flow with the specified density of the air. See [`FluidSettings`].
+    pub fn air_rho(mut self, air_rho: f32) -> Self {
+        self.settings.air_rho = Some(air_rho);
+        self
+    }
+
+    pub fn build(self) -> Result<FluidSettings, FluidSettingsError> {
+        self.settings.validate()?;
+        Ok(self.settings)
+    }
+}
+
+/// Reason why [`FluidSettings`] is invalid.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FluidSettingsError {
+    /// Each dimension of `size` must be a positive multiple of 64 pixels.
+    SizeNotMultipleOf64((u32, u32)),
+    /// `size` must be same between each dimension.
+    NonSquareSize((u32, u32)),
+    /// `dx` must be positive.
+    NonPositiveDx(f32),
+    /// `dt` must be positive.
+    NonPositiveDt(f32),
+    /// `rho` must be positive.
+    NonPositiveRho(f32),
+    /// `gravity` must be finite.
+    NonFiniteGravity(Vec2),
+    /// `initial_fluid_level` must be between 0.0 and 1.0.
+    InitialFluidLevelOutOfRange(f32),
+    /// `viscosity` must be non-negative.
+    NegativeViscosity(f32),
+    /// `air_rho` must be positive if specified.
+    NonPositiveAirRho(f32),
+    /// `surface_tension` must be non-negative.
+    NegativeSurfaceTension(f32),
+}
+
+impl std::fmt::Display for FluidSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SizeNotMultipleOf64(size) => {
+                write!(f, "size must be a positive multiple of 64. {size:?}")
+            }
+            Self::NonSquareSize(size) => {
+                write!(f, "size must be same between size.0 and size.1. {size:?}")
+            }
+            Self::NonPositiveDx(dx) => write!(f, "dx must be positive. {dx}"),
+            Self::NonPositiveDt(dt) => write!(f, "dt must be positive. {dt}"),
+            Self::NonPositiveRho(rho) => write!(f, "rho must be positive. {rho}"),
+            Self::NonFiniteGravity(gravity) => write!(f, "gravity must be finite. {gravity}"),
+            Self::InitialFluidLevelOutOfRange(level) => {
+                write!(
+                    f,
+                    "initial_fluid_level must be between 0.0 and 1.0. {level}"
+                )
+            }
+            Self::NegativeViscosity(viscosity) => {
+                write!(f, "viscosity must be non-negative. {viscosity}")
+            }
+            Self::NonPositiveAirRho(air_rho) => write!(f, "air_rho must be positive. {air_rho}"),
+            Self::NegativeSurfaceTension(surface_tension) => {
+                write!(f, "surface_tension must be non-negative. {surface_tension}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FluidSettingsError {}
+
+/// Global configuration of the simulation, given by [`FluidPlugin`](crate::FluidPlugin).
+/// Workgroup sizes are applied when the pipelines are created, so changing them after the app has started has no effect.
+/// # Examples
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::{
+///     definition::{FluidConfig, FluidSolverSettings, PressureSolver},
+///     FluidPlugin,
+/// };
+///
+/// let plugin = FluidPlugin {
+///     config: FluidConfig {
+///         solver: FluidSolverSettings {
+///             pressure_solver: PressureSolver::RedBlackSor {
+///                 omega: 1.7,
+///                 iterations: 10,
+///             },
+///             ..default()
+///         },
+///         ..default()
+///     },
+/// };
+/// ```
+#[derive(Resource, ExtractResource, Clone, Debug)]
+pub struct FluidConfig {
+    /// Workgroup size per dimension of the passes on grid centers, i.e. `(workgroup_size, workgroup_size, 1)`.
+    pub workgroup_size: u32,
+    /// Workgroup size of the passes on grid faces, i.e. `(1, face_workgroup_size, 1)`.
+    pub face_workgroup_size: u32,
+    /// Maximum number of steps per frame.
+    /// Steps are taken on every tick of [`Time<Fixed>`], so several steps may run on a frame if the frame rate is lower than the fixed timestep.
+    /// Ticks exceeding this limit are dropped to keep slow frames from piling up more work, and a warning is logged.
+    pub max_substeps: u32,
+    /// Solver settings of the fluids without [`FluidSolverSettings`].
+    pub solver: FluidSolverSettings,
+}
+
+impl Default for FluidConfig {
+    fn default() -> Self {
+        Self {
+            workgroup_size: 8,
+            face_workgroup_size: 64,
+            max_substeps: 4,
+            solver: FluidSolverSettings::default(),
+        }
+    }
+}
+
+/// Solver settings of a fluid. Insert it to the entity with [`FluidSettings`] to override [`FluidConfig::solver`].
+/// * `pressure_solver`: Iterative solver of pressure. See [`PressureSolver`].
+/// * `pressure_tolerance`: Enables adaptive number of pressure iterations with the specified tolerance of the residual in unit of [1/s].
+///   The iterations are increased while the residual reported on [`FluidPressureResidual`] exceeds it, and decreased while it is well below,
+///   up to the iterations of `pressure_solver`.
+///   Has no effect unless `residual_interval` is non-zero.
+/// * `residual_interval`: Interval in steps to compute the residual of the pressure solve on GPU. 0 disables it.
+///   The residual is computed at most once per frame, after the steps of the frame.
+/// * `force_kernel_radius`: Standard deviation in pixels of the gaussian kernel which spreads [`LocalForces`] over the grid.
+/// * `levelset_reinitialization_interval`: Interval in steps to re-initialize levelset to a signed distance field. 1 runs it every step and 0 disables it.
+/// * `cfl`: Enables adaptive timestep with the specified CFL number.
+///   Each step of [`FluidSettings::dt`] is split into substeps so that the fluid travels at most `cfl` cells per substep,
+///   based on the maximum velocity computed on GPU. The number of substeps times the steps per frame is still limited by [`FluidConfig::max_substeps`],
+///   so the fluid may travel further than `cfl` cells per substep on slow frames, which is reported by [`FluidTimestep::clamped`].
+///   The chosen timestep can be found on [`FluidTimestep`].
+/// * `advection_scheme`: Scheme to advect the velocity and the levelset. See [`AdvectionScheme`].
+/// * `interpolation`: Kernel to sample the velocity and the levelset in advection. See [`Interpolation`].
+/// * `viscosity_solver`: Solver of the viscous diffusion of the fluids with positive [`FluidSettings::viscosity`]. See [`ViscositySolver`].
+/// * `vorticity_confinement`: Strength of vorticity confinement, which adds a force spinning the fluid around
+///   the local maxima of the vorticity to restore the swirls damped by advection. 0 disables it.
+///   Values around 0.1 to 1 keep the swirls made by [`LocalForces`]; larger values make the flow noisy.
+/// * `extrapolation_layers`: Number of layers of faces around the fluid which the velocity is extrapolated into after the pressure solve,
+///   so that the levelset and the velocity are advected with the fluid velocity near the surface and the obstacles. 0 disables it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct FluidSolverSettings {
+    pub pressure_solver: PressureSolver,
+    pub force_kernel_radius: f32,
+    pub levelset_reinitialization_interval: u32,
+    pub cfl: Option<f32>,
+    pub pressure_tolerance: Option<f32>,
+    pub residual_interval: u32,
+    pub extrapolation_layers: u32,
+    pub advection_scheme: AdvectionScheme,
+    pub interpolation: Interpolation,
+    pub vorticity_confinement: f32,
+    pub viscosity_solver: ViscositySolver,
+}
+
+impl Default for FluidSolverSettings {
+    fn default() -> Self {
+        Self {
+            pressure_solver: PressureSolver::Jacobi { iterations: 5 },
+            force_kernel_radius: 10.0,
+            levelset_reinitialization_interval: 1,
+            cfl: None,
+            pressure_tolerance: None,
+            residual_interval: 0,
+            extrapolation_layers: 4,
+            advection_scheme: AdvectionScheme::default(),
+            interpolation: Interpolation::default(),
+            vorticity_confinement: 0.0,
+            viscosity_solver: ViscositySolver::default(),
+        }
+    }
+}
+
+/// Iterative solver of the pressure Poisson equation.
+/// * `Jacobi`: Each iteration sweeps the grid forward and then backward with Jacobi method.
+/// * `RedBlackSor`: Each iteration updates the cells of one parity of a checkerboard and then the other in place,
+///   over-relaxed by `omega`. `omega` should be in (0, 2); 1 is Gauss–Seidel method.
+///   Converges about twice as fast per sweep as `Jacobi`.
+/// * `Multigrid`: Each iteration runs a V-cycle of geometric multigrid over a hierarchy of grids halved down to about 8 cells.
+///   Each level is smoothed `smoothing` times by red-black Gauss–Seidel before the restriction and after the prolongation,
+///   and the coarsest level is smoothed [`MULTIGRID_COARSEST_SMOOTHING`] times.
+///   Suited to large grids where the other solvers cannot converge within a frame.
+/// * `Pcg`: Preconditioned conjugate gradient method, stopped on GPU once the divergence left by the residual
+///   falls below `tolerance` in unit of [1/s] or after `iterations`. Accurate but the most expensive per iteration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PressureSolver {
+    Jacobi {
+        iterations: u32,
+    },
+    RedBlackSor {
+        omega: f32,
+        iterations: u32,
+    },
+    Multigrid {
+        iterations: u32,
+        smoothing: u32,
+    },
+    Pcg {
+        iterations: u32,
+        tolerance: f32,
+        preconditioner: PcgPreconditioner,
+    },
+}
+
+/// Preconditioner of [`PressureSolver::Pcg`].
+/// * `Jacobi`: Divides the residual by the diagonal of the matrix.
+/// * `IncompletePoisson`: Approximates the inverse of the matrix by its lower and upper parts, which takes fewer iterations than `Jacobi`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PcgPreconditioner {
+    Jacobi,
+    #[default]
+    IncompletePoisson,
+}
+
+/// Scheme to advect the velocity and the levelset.
+/// * `SemiLagrangian`: Traces the grid points back and interpolates the field there. Stable but diffusive.
+/// * `MacCormack`: Corrects the semi-Lagrangian result with the error estimated by advecting it back,
+///   limited to the range of the values around the departure point. Keeps small vortices and sharp surfaces
+///   at about three times the cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AdvectionScheme {
+    #[default]
+    SemiLagrangian,
+    MacCormack,
+}
+
+/// Solver of the viscous diffusion of the velocity.
+/// * `Explicit`: Adds the viscous term of the current velocity. Cheap, but only stable while
+///   `viscosity * dt / dx^2` is below 0.25, so suited to thin fluids such as water.
+///   Steps exceeding the limit are solved by the default `Implicit` solver instead, with a warning.
+/// * `Implicit`: Solves the backward Euler step of the diffusion with Jacobi method,
+///   each iteration sweeping forward and then backward. Stable for any viscosity, such as honey or mud,
+///   though thick fluids need more iterations to reach the full damping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViscositySolver {
+    Explicit,
+    Implicit { iterations: u32 },
+}
+
+impl Default for ViscositySolver {
+    fn default() -> Self {
+        Self::Implicit { iterations: 10 }
+    }
+}
+
+impl ViscositySolver {
+    /// Upper limit of `viscosity * dt / dx^2` below which [`ViscositySolver::Explicit`] is stable.
+    pub const EXPLICIT_STABILITY_LIMIT: f32 = 0.25;
+
+    /// Solver to use on a step with the diffusion number `alpha = viscosity * dt / dx^2`.
+    /// [`ViscositySolver::Explicit`] falls back to the default [`ViscositySolver::Implicit`] where it would diverge.
+    /// ```rust
+    /// use bevy_eulerian_fluid::definition::ViscositySolver;
+    ///
+    /// assert_eq!(ViscositySolver::Explicit.stable_for(0.1), ViscositySolver::Explicit);
+    /// assert_eq!(ViscositySolver::Explicit.stable_for(0.5), ViscositySolver::default());
+    /// ```
+    pub fn stable_for(self, alpha: f32) -> Self {
+        match self {
+            Self::Explicit if alpha >= Self::EXPLICIT_STABILITY_LIMIT => Self::default(),
+            solver => solver,
+        }
+    }
+}
+
+/// Kernel to interpolate the fields between grid points.
+/// * `Bilinear`: Linear in each axis over the 2x2 grid points around. Smooth but diffusive.
+/// * `CatmullRom`: Cubic spline over the 4x4 grid points around. Keeps more detail but may overshoot.
+/// * `MonotoneCubic`: Cubic spline whose slopes are flattened where the data is not monotone,
+///   clamped to the 2x2 grid points around so that it never overshoots.
+///
+/// The kernel applies to the advection. Resampling the fields on a change of [`FluidSettings::size`] is always bilinear.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Bilinear = 0,
+    CatmullRom = 1,
+    MonotoneCubic = 2,
+}
+
+/// Number of smoothing sweeps on the coarsest level of [`PressureSolver::Multigrid`].
+pub const MULTIGRID_COARSEST_SMOOTHING: u32 = 32;
+
+impl PressureSolver {
+    /// Number of iterations per step.
+    pub fn iterations(&self) -> u32 {
+        match *self {
+            PressureSolver::Jacobi { iterations } => iterations,
+            PressureSolver::RedBlackSor { iterations, .. } => iterations,
+            PressureSolver::Multigrid { iterations, .. } => iterations,
+            PressureSolver::Pcg { iterations, .. } => iterations,
+        }
+    }
+}
+
+/// Timestep of the simulation on the latest frame, inserted by the plugin.
+/// * `dt`: Timestep of a substep in unit of [sec]. Smaller than [`FluidSettings::dt`] if the step is split by [`FluidSolverSettings::cfl`].
+/// * `substeps`: Number of substeps each step of [`FluidSettings::dt`] is split into on the frame.
+/// * `clamped`: Whether `substeps` was limited by [`FluidConfig::max_substeps`] below the number required by [`FluidSolverSettings::cfl`].
+///   The fluid may travel further than `cfl` cells per substep while this is set.
+/// * `max_velocity`: Maximum velocity of the fluid in unit of [m/s], read back from GPU. Updated only while adaptive timestep is enabled.
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::FluidTimestep;
+///
+/// fn log_timestep(query: Query<&FluidTimestep, Changed<FluidTimestep>>) {
+///     for timestep in &query {
+///         info!("{} substeps of {} sec", timestep.substeps, timestep.dt);
+///     }
+/// }
+/// ```
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct FluidTimestep {
+    pub dt: f32,
+    pub substeps: u32,
+    pub clamped: bool,
+    pub max_velocity: f32,
+}
+
+/// Residual of the pressure solve, inserted by the plugin once it is read back from GPU.
+/// Updated every [`FluidSolverSettings::residual_interval`] steps, a few frames later than it is computed.
+/// * `residual`: Maximum of |∇·u| over the fluid cells after the velocity is projected, in unit of [1/s].
+/// * `iterations`: Number of pressure iterations of the step which the residual is computed on.
+///   With [`PressureSolver::Pcg`], the iterations taken until convergence.
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::FluidPressureResidual;
+///
+/// fn log_residual(query: Query<&FluidPressureResidual, Changed<FluidPressureResidual>>) {
+///     for residual in &query {
+///         info!("residual {} after {} iterations", residual.residual, residual.iterations);
+///     }
+/// }
+/// ```
+#[derive(Component, Clone, Copy, Debug)]
+pub struct FluidPressureResidual {
+    pub residual: f32,
+    pub iterations: u32,
+}
+
+/// Resets the simulation of `entity` to its initial condition without despawning it.
+/// Velocity, pressure, divergence, levelset, grid labels and jump flooding seeds are initialized again on the next frame.
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::{FluidSettings, ResetFluid};
+///
+/// fn restart_level(query: Query<Entity, With<FluidSettings>>, mut events: EventWriter<ResetFluid>) {
+///     for entity in &query {
+///         events.send(ResetFluid { entity });
+///     }
+/// }
+/// ```
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ResetFluid {
+    pub entity: Entity,
+}
+
+/// Pauses the simulation of the entity while the velocity, levelset and so on are kept as they are.
+/// Remove the component to resume the simulation.
+/// A paused simulation advances `steps` steps, one step per frame, and then stays paused.
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::{FluidPaused, FluidSettings};
+///
+/// fn step_once(mut query: Query<&mut FluidPaused, With<FluidSettings>>) {
+///     for mut paused in &mut query {
+///         paused.steps += 1;
+///     }
+/// }
+/// ```
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct FluidPaused {
+    pub steps: u32,
+}
+
+/// Controls the simulation of all fluids.
+/// While `paused` is `true`, every fluid is paused as if it had [`FluidPaused`],
+/// and `steps` advances all of them by the specified number of steps, one step per frame.
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::SimulationControl;
+///
+/// fn toggle_pause(keys: Res<ButtonInput<KeyCode>>, mut control: ResMut<SimulationControl>) {
+///     if keys.just_pressed(KeyCode::Space) {
+///         control.paused = !control.paused;
+///     }
+///     if keys.just_pressed(KeyCode::Period) {
+///         control.steps += 1;
+///     }
+/// }
+/// ```
+#[derive(Resource, Clone, Copy, Default, Debug)]
+pub struct SimulationControl {
+    pub paused: bool,
+    pub steps: u32,
+}
+
+/// Sent when the render world has initialized the simulation of `entity`.
+/// From this point on, the textures of the entity hold valid simulation results,
+/// so this is the right moment to attach visualization or gameplay logic.
+/// It is sent again after the simulation is reset by [`ResetFluid`].
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::{FluidInitialized, VelocityTextures};
+///
+/// fn on_fluid_initialized(
+///     mut events: EventReader<FluidInitialized>,
+///     query: Query<&VelocityTextures>,
+/// ) {
+///     for event in events.read() {
+///         let Ok(velocity_textures) = query.get(event.entity) else {
+///             continue;
+///         };
+///         // Attach materials using velocity_textures.u0 and velocity_textures.v0.
+///     }
+/// }
+/// ```
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FluidInitialized {
+    pub entity: Entity,
+}
+
+/// Stage of the simulation in which a [`FluidError`] occurred.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FluidErrorStage {
+    /// Creating bind groups from the simulation textures and buffers.
+    /// Typically a texture which has not been uploaded to GPU yet.
+    PrepareBindGroups,
+    /// Compiling the compute pipelines of the simulation, e.g. a shader error.
+    PipelineCompilation,
+}
+
+/// Sent when the render world fails to prepare or run the simulation.
+/// The failed work is skipped for the frame instead of panicking the render thread.
+/// `entity` is `None` if the failure is not specific to a fluid, such as a shader compilation error, which stops every simulation until it is resolved.
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::definition::FluidError;
+///
+/// fn report_fluid_errors(mut events: EventReader<FluidError>) {
+///     for error in events.read() {
+///         error!("{:?} failed on {:?}: {}", error.stage, error.entity, error.message);
+///     }
+/// }
+/// ```
+#[derive(Event, Clone, Debug)]
+pub struct FluidError {
+    pub entity: Option<Entity>,
+    pub stage: FluidErrorStage,
+    pub message: String,
 }
 
 #[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Default)]
@@ -81,6 +716,14 @@ pub struct SimulationUniform {
     pub rho: f32,
     pub gravity: Vec2,
     pub initial_fluid_level: f32,
+    pub force_kernel_radius: f32,
+    pub sor_omega: f32,
+    pub pcg_tolerance: f32,
+    pub interpolation: u32,
+    pub vorticity_confinement: f32,
+    pub viscosity: f32,
+    pub air_rho: f32,
+    pub surface_tension: f32,
 }
 
 /// Fluid velocity field.
@@ -124,6 +767,11 @@ pub struct LevelsetTextures {
     // grid label which describe grid state. 0: empty, 1: fluid, 2: solid.
     #[storage_texture(1, image_format = R32Uint, access = ReadWrite)]
     pub grid_label: Handle<Image>,
+    // fraction of each face not covered by obstacles. 0: closed, 1: open.
+    #[storage_texture(2, image_format = R32Float, access = ReadWrite)]
+    pub u_fraction: Handle<Image>,
+    #[storage_texture(3, image_format = R32Float, access = ReadWrite)]
+    pub v_fraction: Handle<Image>,
 }
 
 #[derive(Component, Clone, ExtractComponent, AsBindGroup)]
@@ -175,6 +823,37 @@ pub struct JumpFloodingUniformBuffer {
     pub buffer: Vec<UniformBuffer<JumpFloodingUniform>>,
 }
 
+/// Textures allocated for a simulation.
+/// See also [`FluidTexturePool`](crate::setup_components::FluidTexturePool).
+#[derive(Bundle, Clone)]
+pub struct FluidTextures {
+    pub velocity_textures: VelocityTextures,
+    pub pressure_textures: PressureTextures,
+    pub divergence_textures: DivergenceTextures,
+    pub levelset_textures: LevelsetTextures,
+    pub jump_flooding_seeds_textures: JumpFloodingSeedsTextures,
+}
+
+impl FluidTextures {
+    pub(crate) fn handles(&self) -> [&Handle<Image>; 13] {
+        [
+            &self.velocity_textures.u0,
+            &self.velocity_textures.v0,
+            &self.velocity_textures.u1,
+            &self.velocity_textures.v1,
+            &self.pressure_textures.p0,
+            &self.pressure_textures.p1,
+            &self.divergence_textures.div,
+            &self.levelset_textures.levelset,
+            &self.levelset_textures.grid_label,
+            &self.levelset_textures.u_fraction,
+            &self.levelset_textures.v_fraction,
+            &self.jump_flooding_seeds_textures.jump_flooding_seeds_x,
+            &self.jump_flooding_seeds_textures.jump_flooding_seeds_y,
+        ]
+    }
+}
+
 #[derive(Bundle)]
 pub struct FluidSimulationBundle {
     pub velocity_textures: VelocityTextures,
@@ -184,3 +863,145 @@ pub struct FluidSimulationBundle {
     pub local_forces: LocalForces,
     pub jump_flooding_seeds_textures: JumpFloodingSeedsTextures,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> FluidSettingsBuilder {
+        FluidSettings::builder()
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(builder().build().is_ok());
+    }
+
+    #[test]
+    fn builder_sets_fields() {
+        let settings = builder()
+            .size((128, 128))
+            .dx(0.5)
+            .dt(0.1)
+            .rho(1.0)
+            .gravity(Vec2::NEG_Y)
+            .initial_fluid_level(0.5)
+            .build()
+            .unwrap();
+        assert_eq!(settings.size, (128, 128));
+        assert_eq!(settings.dx, 0.5);
+        assert_eq!(settings.dt, 0.1);
+        assert_eq!(settings.rho, 1.0);
+        assert_eq!(settings.gravity, Vec2::NEG_Y);
+        assert_eq!(settings.initial_fluid_level, 0.5);
+    }
+
+    #[test]
+    fn validate_checks_settings_built_without_builder() {
+        let mut settings = builder().build().unwrap();
+        settings.dx = f32::NAN;
+        assert!(matches!(
+            settings.validate(),
+            Err(FluidSettingsError::NonPositiveDx(_))
+        ));
+    }
+
+    #[test]
+    fn presets_set_density() {
+        let water = builder().water().build().unwrap();
+        assert_eq!(water.rho, FluidSettingsBuilder::WATER_DENSITY);
+        let air = builder().air().build().unwrap();
+        assert_eq!(air.rho, FluidSettingsBuilder::AIR_DENSITY);
+        let oil = builder().oil().build().unwrap();
+        assert_eq!(oil.rho, FluidSettingsBuilder::OIL_DENSITY);
+    }
+
+    #[test]
+    fn rejects_size_not_multiple_of_64() {
+        assert_eq!(
+            builder().size((100, 100)).build().unwrap_err(),
+            FluidSettingsError::SizeNotMultipleOf64((100, 100))
+        );
+        assert_eq!(
+            builder().size((0, 0)).build().unwrap_err(),
+            FluidSettingsError::SizeNotMultipleOf64((0, 0))
+        );
+    }
+
+    #[test]
+    fn rejects_non_square_size() {
+        assert_eq!(
+            builder().size((128, 64)).build().unwrap_err(),
+            FluidSettingsError::NonSquareSize((128, 64))
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_dx() {
+        assert_eq!(
+            builder().dx(0.0).build().unwrap_err(),
+            FluidSettingsError::NonPositiveDx(0.0)
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_dt() {
+        assert_eq!(
+            builder().dt(0.0).build().unwrap_err(),
+            FluidSettingsError::NonPositiveDt(0.0)
+        );
+        assert_eq!(
+            builder().dt(-0.1).build().unwrap_err(),
+            FluidSettingsError::NonPositiveDt(-0.1)
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_rho() {
+        assert_eq!(
+            builder().rho(-1.0).build().unwrap_err(),
+            FluidSettingsError::NonPositiveRho(-1.0)
+        );
+    }
+
+    #[test]
+    fn rejects_non_finite_gravity() {
+        let gravity = Vec2::new(0.0, f32::INFINITY);
+        assert_eq!(
+            builder().gravity(gravity).build().unwrap_err(),
+            FluidSettingsError::NonFiniteGravity(gravity)
+        );
+    }
+
+    #[test]
+    fn rejects_initial_fluid_level_out_of_range() {
+        assert_eq!(
+            builder().initial_fluid_level(1.5).build().unwrap_err(),
+            FluidSettingsError::InitialFluidLevelOutOfRange(1.5)
+        );
+    }
+
+    #[test]
+    fn rejects_negative_viscosity() {
+        assert_eq!(
+            builder().viscosity(-1.0e-6).build().unwrap_err(),
+            FluidSettingsError::NegativeViscosity(-1.0e-6)
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_air_rho() {
+        assert_eq!(
+            builder().air_rho(0.0).build().unwrap_err(),
+            FluidSettingsError::NonPositiveAirRho(0.0)
+        );
+    }
+
+    #[test]
+    fn rejects_negative_surface_tension() {
+        assert_eq!(
+            builder().surface_tension(-0.1).build().unwrap_err(),
+            FluidSettingsError::NegativeSurfaceTension(-0.1)
+        );
+    }
+}
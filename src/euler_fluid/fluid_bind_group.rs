@@ -1,34 +1,48 @@
 use std::borrow::Cow;
+use std::sync::atomic::AtomicU8;
+use std::sync::{Arc, Mutex};
 
+use bevy::ecs::system::{StaticSystemParam, SystemParamItem};
 use bevy::render::extract_component::ExtractComponent;
 use bevy::render::render_resource::UniformBuffer;
 use bevy::render::renderer::RenderQueue;
-use bevy::render::storage::GpuShaderStorageBuffer;
+use bevy::render::sync_world::MainEntity;
+use bevy::utils::HashSet;
 use bevy::{
     prelude::*,
     render::{
         extract_component::{ComponentUniforms, DynamicUniformIndex},
         render_asset::RenderAssets,
         render_resource::{
-            binding_types::uniform_buffer, AsBindGroup, BindGroup, BindGroupEntries,
-            BindGroupLayout, BindGroupLayoutEntries, CachedComputePipelineId,
-            ComputePipelineDescriptor, PipelineCache, ShaderStages,
+            binding_types::{
+                storage_buffer, storage_buffer_sized, texture_storage_2d, uniform_buffer,
+            },
+            AsBindGroup, AsBindGroupError, BindGroup, BindGroupEntries, BindGroupLayout,
+            BindGroupLayoutEntries, Buffer, BufferDescriptor, BufferUsages,
+            CachedComputePipelineId, ComputePipelineDescriptor, Extent3d, PipelineCache,
+            ShaderDefVal, ShaderStages, StorageTextureAccess, Texture, TextureDescriptor,
+            TextureDimension, TextureFormat, TextureUsages,
         },
         renderer::RenderDevice,
-        texture::{FallbackImage, GpuImage},
+        texture::GpuImage,
     },
 };
 
+use super::render_node::FluidErrorQueue;
+use super::setup_components::ResolvedSolverSettings;
+
 use super::definition::{
-    DivergenceTextures, FluidSettings, JumpFloodingSeedsTextures, JumpFloodingUniform,
-    JumpFloodingUniformBuffer, LevelsetTextures, LocalForces, Obstacles, PressureTextures,
-    SimulationUniform, VelocityTextures,
+    AdvectionScheme, DivergenceTextures, FluidConfig, FluidError, FluidErrorStage, FluidSettings,
+    JumpFloodingSeedsTextures, JumpFloodingUniform, JumpFloodingUniformBuffer, LevelsetTextures,
+    LocalForces, Obstacles, PressureSolver, PressureTextures, SimulationUniform, VelocityTextures,
 };
 
 pub(super) const INITIALIZE_GRID_CENTER_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0xD9C0123A6DC94D01AA0D8BEF9784EC16);
 pub(super) const INITIALIZE_VELOCITY_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0xE517B3F694A9446B970368B971BF631E);
+pub(super) const CLEAR_GRID_CENTER_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x7A3F5C1E08B24D6F9E2C4B8D1F0A6E35);
 
 pub(super) const UPDATE_GRID_LABEL_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x3B7E226FADA549C1A6662BCED3B83535);
@@ -40,8 +54,16 @@ pub(super) const DIVERGENCE_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0xD31C2EF5DE254DC097F20C813A5A0C6D);
 pub(super) const JACOBI_ITERATION_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x8BB3FAA20BC24FB4B790C11A8A2F8E63);
+pub(super) const RED_BLACK_SOR_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5C1D7E93A04F4B28B6E0F2A9C83D4E17);
+pub(super) const MULTIGRID_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0xA7E24C0B9D3F4861B5F1C8E6027D3A49);
+pub(super) const PCG_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x94B3E6D15A2C4F0E8D7A1B6C3E5F2084);
 pub(super) const SOLVE_VELOCITY_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x1B95362358B242BCA68804444013F99E);
+pub(super) const EXTRAPOLATE_VELOCITY_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x8E4F1A7C2B6D4390A5D3E09B71C62F4A);
 
 pub(super) const RECOMPUTE_LEVELSET_INITIALIZE_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0xAFC6EC29854A413CB0E4113506AE2254);
@@ -51,22 +73,74 @@ pub(super) const RECOMPUTE_LEVELSET_SDF_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x45710E52048449A5A59D382284974B38);
 pub(super) const ADVECT_LEVELSET_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0x4165F4894F76420E8D67FC83E3466ACA);
+pub(super) const MAC_CORMACK_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x0D5B8E72A9F34C61B4E7C2A19F83D605);
+pub(super) const VORTICITY_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5A3E9C170B8D4F62A1C7E4B29D06F83B);
+pub(super) const VISCOSITY_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0xB47D2E90C6A3415F8E1D0A7C93F25B64);
+
+pub(super) const RESAMPLE_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x2E1D6A4C9B8F47E3A05C7D13F6B2E948);
+
+pub(super) const RESIDUAL_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x2F8C4A6E913B4D57A0E6C3B9D71F5A82);
+pub(super) const MAX_VELOCITY_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x6D0E3B9A51C84F27B8E4A2C7153F9D60);
 
 #[derive(Resource)]
 pub(crate) struct FluidPipelines {
     pub initialize_velocity_pipeline: CachedComputePipelineId,
     pub initialize_grid_center_pipeline: CachedComputePipelineId,
+    pub clear_grid_center_pipeline: CachedComputePipelineId,
     pub update_grid_label_pipeline: CachedComputePipelineId,
+    pub update_face_fraction_pipeline: CachedComputePipelineId,
     pub advection_pipeline: CachedComputePipelineId,
     pub add_force_pipeline: CachedComputePipelineId,
+    pub vorticity_pipeline: CachedComputePipelineId,
+    pub vorticity_confinement_pipeline: CachedComputePipelineId,
+    pub viscosity_copy_pipeline: CachedComputePipelineId,
+    pub viscosity_explicit_pipeline: CachedComputePipelineId,
+    pub viscosity_jacobi_pipeline: CachedComputePipelineId,
+    pub viscosity_jacobi_reverse_pipeline: CachedComputePipelineId,
     pub divergence_pipeline: CachedComputePipelineId,
     pub jacobi_iteration_pipeline: CachedComputePipelineId,
     pub jacobi_iteration_reverse_pipeline: CachedComputePipelineId,
+    pub red_black_sor_red_pipeline: CachedComputePipelineId,
+    pub red_black_sor_black_pipeline: CachedComputePipelineId,
+    pub multigrid_restrict_finest_pipeline: CachedComputePipelineId,
+    pub multigrid_restrict_coarse_pipeline: CachedComputePipelineId,
+    pub multigrid_prolongate_pipeline: CachedComputePipelineId,
+    pub multigrid_smooth_red_pipeline: CachedComputePipelineId,
+    pub multigrid_smooth_black_pipeline: CachedComputePipelineId,
+    pub pcg_initialize_pipeline: CachedComputePipelineId,
+    pub pcg_precondition_jacobi_pipeline: CachedComputePipelineId,
+    pub pcg_precondition_incomplete_poisson_upper_pipeline: CachedComputePipelineId,
+    pub pcg_precondition_incomplete_poisson_lower_pipeline: CachedComputePipelineId,
+    pub pcg_reduce_initial_pipeline: CachedComputePipelineId,
+    pub pcg_update_direction_pipeline: CachedComputePipelineId,
+    pub pcg_apply_pipeline: CachedComputePipelineId,
+    pub pcg_reduce_alpha_pipeline: CachedComputePipelineId,
+    pub pcg_update_pipeline: CachedComputePipelineId,
+    pub pcg_reduce_beta_pipeline: CachedComputePipelineId,
     pub solve_velocity_pipeline: CachedComputePipelineId,
+    pub initialize_valid_faces_pipeline: CachedComputePipelineId,
+    pub extrapolate_velocity_pipeline: CachedComputePipelineId,
     pub recompute_levelset_initialization_pipeline: CachedComputePipelineId,
     pub recompute_levelset_iteration_pipeline: CachedComputePipelineId,
     pub recompute_levelset_solve_pipeline: CachedComputePipelineId,
     pub advect_levelset_pipeline: CachedComputePipelineId,
+    pub mac_cormack_velocity_forward_pipeline: CachedComputePipelineId,
+    pub mac_cormack_velocity_backward_pipeline: CachedComputePipelineId,
+    pub mac_cormack_velocity_correct_pipeline: CachedComputePipelineId,
+    pub mac_cormack_levelset_forward_pipeline: CachedComputePipelineId,
+    pub mac_cormack_levelset_backward_pipeline: CachedComputePipelineId,
+    pub mac_cormack_levelset_correct_pipeline: CachedComputePipelineId,
+    pub mac_cormack_levelset_copy_pipeline: CachedComputePipelineId,
+    pub resample_velocity_pipeline: CachedComputePipelineId,
+    pub resample_levelset_pipeline: CachedComputePipelineId,
+    pub max_velocity_pipeline: CachedComputePipelineId,
+    pub residual_pipeline: CachedComputePipelineId,
     velocity_bind_group_layout: BindGroupLayout,
     pressure_bind_group_layout: BindGroupLayout,
     divergence_bind_group_layout: BindGroupLayout,
@@ -76,12 +150,24 @@ pub(crate) struct FluidPipelines {
     obstacles_bind_group_layout: BindGroupLayout,
     jump_flooding_seeds_bind_group_layout: BindGroupLayout,
     jump_flooding_uniform_bind_group_layout: BindGroupLayout,
+    readback_bind_group_layout: BindGroupLayout,
+    multigrid_level_bind_group_layout: BindGroupLayout,
+    pcg_bind_group_layout: BindGroupLayout,
+    extrapolation_bind_group_layout: BindGroupLayout,
+    mac_cormack_bind_group_layout: BindGroupLayout,
+    vorticity_bind_group_layout: BindGroupLayout,
+    viscosity_bind_group_layout: BindGroupLayout,
 }
 
 impl FromWorld for FluidPipelines {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
         let pipeline_cache = world.resource::<PipelineCache>();
+        let config = world.resource::<FluidConfig>();
+        let shader_defs = vec![
+            ShaderDefVal::UInt("WORKGROUP_SIZE".into(), config.workgroup_size),
+            ShaderDefVal::UInt("FACE_WORKGROUP_SIZE".into(), config.face_workgroup_size),
+        ];
 
         let uniform_bind_group_layout = render_device.create_bind_group_layout(
             Some("Create uniform bind group layout"),
@@ -106,209 +192,687 @@ impl FromWorld for FluidPipelines {
             ),
         );
 
-        let initialize_velocity_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue InitializeVelocityPipeline")),
-                layout: vec![velocity_bind_group_layout.clone()],
-                push_constant_ranges: vec![],
-                shader: INITIALIZE_VELOCITY_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("initialize_velocity"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let readback_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create ReadbackBindGroupLayout"),
+            &BindGroupLayoutEntries::single(
+                ShaderStages::COMPUTE,
+                storage_buffer::<[u32; 3]>(false),
+            ),
+        );
 
-        let initialize_grid_center_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue InitializeGridCenterPipeline")),
-                layout: vec![
-                    levelset_bind_group_layout.clone(),
-                    uniform_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: INITIALIZE_GRID_CENTER_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("initialize_grid_center"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let multigrid_level_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create MultigridLevelBindGroupLayout"),
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Uint, StorageTextureAccess::ReadWrite),
+                ),
+            ),
+        );
 
-        let update_grid_label_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue UpdateGridLabelPipeline")),
-                layout: vec![
-                    velocity_bind_group_layout.clone(),
-                    levelset_bind_group_layout.clone(),
-                    obstacles_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: UPDATE_GRID_LABEL_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("update_grid_label"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let pcg_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create PcgBindGroupLayout"),
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Uint, StorageTextureAccess::ReadWrite),
+                    storage_buffer_sized(false, None),
+                    storage_buffer_sized(false, None),
+                    storage_buffer_sized(false, None),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                ),
+            ),
+        );
 
-        let advection_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-            label: Some(Cow::from("Queue AdvectionPipeline")),
-            layout: vec![
-                velocity_bind_group_layout.clone(),
-                levelset_bind_group_layout.clone(),
-                uniform_bind_group_layout.clone(),
-            ],
-            push_constant_ranges: vec![],
-            shader: ADVECTION_SHADER_HANDLE,
-            shader_defs: vec![],
-            entry_point: Cow::from("advection"),
-            zero_initialize_workgroup_memory: false,
-        });
+        let extrapolation_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create ExtrapolationBindGroupLayout"),
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_storage_2d(TextureFormat::R32Uint, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Uint, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Uint, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Uint, StorageTextureAccess::ReadWrite),
+                ),
+            ),
+        );
 
-        let add_force_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-            label: Some(Cow::from("Queue AddForcePipeline")),
-            layout: vec![
-                velocity_bind_group_layout.clone(),
-                uniform_bind_group_layout.clone(),
-                local_forces_bind_group_layout.clone(),
-                levelset_bind_group_layout.clone(),
-            ],
-            push_constant_ranges: vec![],
-            shader: ADD_FORCE_SHADER_HANDLE,
-            shader_defs: vec![],
-            entry_point: Cow::from("add_force"),
-            zero_initialize_workgroup_memory: false,
-        });
+        let mac_cormack_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create MacCormackBindGroupLayout"),
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                ),
+            ),
+        );
 
-        let divergence_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue DivergencePipeline")),
-                layout: vec![
-                    velocity_bind_group_layout.clone(),
-                    divergence_bind_group_layout.clone(),
-                    levelset_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: DIVERGENCE_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("divergence"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let vorticity_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create VorticityBindGroupLayout"),
+            &BindGroupLayoutEntries::single(
+                ShaderStages::COMPUTE,
+                texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+            ),
+        );
 
-        let jacobi_iteration_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue JacobiIterationPipeline")),
-                layout: vec![
-                    uniform_bind_group_layout.clone(),
-                    pressure_bind_group_layout.clone(),
-                    divergence_bind_group_layout.clone(),
-                    levelset_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: JACOBI_ITERATION_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("jacobi_iteration"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let viscosity_bind_group_layout = render_device.create_bind_group_layout(
+            Some("Create ViscosityBindGroupLayout"),
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                    texture_storage_2d(TextureFormat::R32Float, StorageTextureAccess::ReadWrite),
+                ),
+            ),
+        );
 
-        let jacobi_iteration_reverse_pipeline =
+        let queue = |label: &'static str,
+                     shader: Handle<Shader>,
+                     entry_point: &'static str,
+                     layout: &[&BindGroupLayout]| {
             pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue JacobiIterationReversePipeline")),
-                layout: vec![
-                    uniform_bind_group_layout.clone(),
-                    pressure_bind_group_layout.clone(),
-                    divergence_bind_group_layout.clone(),
-                    levelset_bind_group_layout.clone(),
-                ],
+                label: Some(Cow::from(label)),
+                layout: layout.iter().map(|&layout| layout.clone()).collect(),
                 push_constant_ranges: vec![],
-                shader: JACOBI_ITERATION_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("jacobi_iteration_reverse"),
+                shader,
+                shader_defs: shader_defs.clone(),
+                entry_point: Cow::from(entry_point),
                 zero_initialize_workgroup_memory: false,
-            });
+            })
+        };
 
-        let solve_velocity_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue SolveVelocityPipeline")),
-                layout: vec![
-                    velocity_bind_group_layout.clone(),
-                    uniform_bind_group_layout.clone(),
-                    pressure_bind_group_layout.clone(),
-                    levelset_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: SOLVE_VELOCITY_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("solve_velocity"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let initialize_velocity_pipeline = queue(
+            "Queue InitializeVelocityPipeline",
+            INITIALIZE_VELOCITY_SHADER_HANDLE,
+            "initialize_velocity",
+            &[&velocity_bind_group_layout],
+        );
 
-        let recompute_levelset_initialization_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue RecomputeLevelsetInitializationPipeline")),
-                layout: vec![
-                    levelset_bind_group_layout.clone(),
-                    jump_flooding_seeds_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: RECOMPUTE_LEVELSET_INITIALIZE_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("initialize"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let initialize_grid_center_pipeline = queue(
+            "Queue InitializeGridCenterPipeline",
+            INITIALIZE_GRID_CENTER_SHADER_HANDLE,
+            "initialize_grid_center",
+            &[&levelset_bind_group_layout, &uniform_bind_group_layout],
+        );
 
-        let recompute_levelset_iteration_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue RecomputeLevelsetIteratePipeline")),
-                layout: vec![
-                    jump_flooding_seeds_bind_group_layout.clone(),
-                    jump_flooding_uniform_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: RECOMPUTE_LEVELSET_ITERATE_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("iterate"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let clear_grid_center_pipeline = queue(
+            "Queue ClearGridCenterPipeline",
+            CLEAR_GRID_CENTER_SHADER_HANDLE,
+            "clear_grid_center",
+            &[
+                &pressure_bind_group_layout,
+                &divergence_bind_group_layout,
+                &jump_flooding_seeds_bind_group_layout,
+            ],
+        );
 
-        let recompute_levelset_solve_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue RecomputeLevelsetSolvePipeline")),
-                layout: vec![
-                    levelset_bind_group_layout.clone(),
-                    jump_flooding_seeds_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: RECOMPUTE_LEVELSET_SDF_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("calculate_sdf"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let update_grid_label_pipeline = queue(
+            "Queue UpdateGridLabelPipeline",
+            UPDATE_GRID_LABEL_SHADER_HANDLE,
+            "update_grid_label",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &obstacles_bind_group_layout,
+            ],
+        );
 
-        let advect_levelset_pipeline =
-            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: Some(Cow::from("Queue AdvectLevelsetPipeline")),
-                layout: vec![
-                    velocity_bind_group_layout.clone(),
-                    levelset_bind_group_layout.clone(),
-                    uniform_bind_group_layout.clone(),
-                ],
-                push_constant_ranges: vec![],
-                shader: ADVECT_LEVELSET_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: Cow::from("advect_levelset"),
-                zero_initialize_workgroup_memory: false,
-            });
+        let update_face_fraction_pipeline = queue(
+            "Queue UpdateFaceFractionPipeline",
+            UPDATE_GRID_LABEL_SHADER_HANDLE,
+            "update_face_fraction",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &obstacles_bind_group_layout,
+            ],
+        );
+
+        let advection_pipeline = queue(
+            "Queue AdvectionPipeline",
+            ADVECTION_SHADER_HANDLE,
+            "advection",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+            ],
+        );
+
+        let add_force_pipeline = queue(
+            "Queue AddForcePipeline",
+            ADD_FORCE_SHADER_HANDLE,
+            "add_force",
+            &[
+                &velocity_bind_group_layout,
+                &uniform_bind_group_layout,
+                &local_forces_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let vorticity_pipeline = queue(
+            "Queue VorticityPipeline",
+            VORTICITY_SHADER_HANDLE,
+            "vorticity",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &vorticity_bind_group_layout,
+            ],
+        );
+
+        let vorticity_confinement_pipeline = queue(
+            "Queue VorticityConfinementPipeline",
+            VORTICITY_SHADER_HANDLE,
+            "vorticity_confinement",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &vorticity_bind_group_layout,
+            ],
+        );
+
+        let viscosity_copy_pipeline = queue(
+            "Queue ViscosityCopyPipeline",
+            VISCOSITY_SHADER_HANDLE,
+            "viscosity_copy",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &viscosity_bind_group_layout,
+            ],
+        );
+
+        let viscosity_explicit_pipeline = queue(
+            "Queue ViscosityExplicitPipeline",
+            VISCOSITY_SHADER_HANDLE,
+            "viscosity_explicit",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &viscosity_bind_group_layout,
+            ],
+        );
+
+        let viscosity_jacobi_pipeline = queue(
+            "Queue ViscosityJacobiPipeline",
+            VISCOSITY_SHADER_HANDLE,
+            "viscosity_jacobi",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &viscosity_bind_group_layout,
+            ],
+        );
+
+        let viscosity_jacobi_reverse_pipeline = queue(
+            "Queue ViscosityJacobiReversePipeline",
+            VISCOSITY_SHADER_HANDLE,
+            "viscosity_jacobi_reverse",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &viscosity_bind_group_layout,
+            ],
+        );
+
+        let divergence_pipeline = queue(
+            "Queue DivergencePipeline",
+            DIVERGENCE_SHADER_HANDLE,
+            "divergence",
+            &[
+                &velocity_bind_group_layout,
+                &divergence_bind_group_layout,
+                &levelset_bind_group_layout,
+                &obstacles_bind_group_layout,
+            ],
+        );
+
+        let jacobi_iteration_pipeline = queue(
+            "Queue JacobiIterationPipeline",
+            JACOBI_ITERATION_SHADER_HANDLE,
+            "jacobi_iteration",
+            &[
+                &uniform_bind_group_layout,
+                &pressure_bind_group_layout,
+                &divergence_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let jacobi_iteration_reverse_pipeline = queue(
+            "Queue JacobiIterationReversePipeline",
+            JACOBI_ITERATION_SHADER_HANDLE,
+            "jacobi_iteration_reverse",
+            &[
+                &uniform_bind_group_layout,
+                &pressure_bind_group_layout,
+                &divergence_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let red_black_sor_red_pipeline = queue(
+            "Queue RedBlackSorRedPipeline",
+            RED_BLACK_SOR_SHADER_HANDLE,
+            "red_black_sor_red",
+            &[
+                &uniform_bind_group_layout,
+                &pressure_bind_group_layout,
+                &divergence_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let red_black_sor_black_pipeline = queue(
+            "Queue RedBlackSorBlackPipeline",
+            RED_BLACK_SOR_SHADER_HANDLE,
+            "red_black_sor_black",
+            &[
+                &uniform_bind_group_layout,
+                &pressure_bind_group_layout,
+                &divergence_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let multigrid_restrict_finest_pipeline = queue(
+            "Queue MultigridRestrictFinestPipeline",
+            MULTIGRID_SHADER_HANDLE,
+            "restrict_finest",
+            &[
+                &uniform_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let multigrid_restrict_coarse_pipeline = queue(
+            "Queue MultigridRestrictCoarsePipeline",
+            MULTIGRID_SHADER_HANDLE,
+            "restrict_coarse",
+            &[
+                &uniform_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+            ],
+        );
+
+        let multigrid_prolongate_pipeline = queue(
+            "Queue MultigridProlongatePipeline",
+            MULTIGRID_SHADER_HANDLE,
+            "prolongate",
+            &[
+                &uniform_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+            ],
+        );
+
+        let multigrid_smooth_red_pipeline = queue(
+            "Queue MultigridSmoothRedPipeline",
+            MULTIGRID_SHADER_HANDLE,
+            "smooth_red",
+            &[
+                &uniform_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+            ],
+        );
+
+        let multigrid_smooth_black_pipeline = queue(
+            "Queue MultigridSmoothBlackPipeline",
+            MULTIGRID_SHADER_HANDLE,
+            "smooth_black",
+            &[
+                &uniform_bind_group_layout,
+                &multigrid_level_bind_group_layout,
+            ],
+        );
+
+        let pcg_initialize_pipeline = queue(
+            "Queue PcgInitializePipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_initialize",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_precondition_jacobi_pipeline = queue(
+            "Queue PcgPreconditionJacobiPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_precondition_jacobi",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_precondition_incomplete_poisson_upper_pipeline = queue(
+            "Queue PcgPreconditionIncompletePoissonUpperPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_precondition_incomplete_poisson_upper",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_precondition_incomplete_poisson_lower_pipeline = queue(
+            "Queue PcgPreconditionIncompletePoissonLowerPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_precondition_incomplete_poisson_lower",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_reduce_initial_pipeline = queue(
+            "Queue PcgReduceInitialPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_reduce_initial",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_update_direction_pipeline = queue(
+            "Queue PcgUpdateDirectionPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_update_direction",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_apply_pipeline = queue(
+            "Queue PcgApplyPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_apply",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_reduce_alpha_pipeline = queue(
+            "Queue PcgReduceAlphaPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_reduce_alpha",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_update_pipeline = queue(
+            "Queue PcgUpdatePipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_update",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let pcg_reduce_beta_pipeline = queue(
+            "Queue PcgReduceBetaPipeline",
+            PCG_SHADER_HANDLE,
+            "pcg_reduce_beta",
+            &[&uniform_bind_group_layout, &pcg_bind_group_layout],
+        );
+
+        let solve_velocity_pipeline = queue(
+            "Queue SolveVelocityPipeline",
+            SOLVE_VELOCITY_SHADER_HANDLE,
+            "solve_velocity",
+            &[
+                &velocity_bind_group_layout,
+                &uniform_bind_group_layout,
+                &pressure_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let initialize_valid_faces_pipeline = queue(
+            "Queue InitializeValidFacesPipeline",
+            EXTRAPOLATE_VELOCITY_SHADER_HANDLE,
+            "initialize_valid_faces",
+            &[
+                &velocity_bind_group_layout,
+                &extrapolation_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+            ],
+        );
+
+        let extrapolate_velocity_pipeline = queue(
+            "Queue ExtrapolateVelocityPipeline",
+            EXTRAPOLATE_VELOCITY_SHADER_HANDLE,
+            "extrapolate_velocity",
+            &[
+                &velocity_bind_group_layout,
+                &extrapolation_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let recompute_levelset_initialization_pipeline = queue(
+            "Queue RecomputeLevelsetInitializationPipeline",
+            RECOMPUTE_LEVELSET_INITIALIZE_SHADER_HANDLE,
+            "initialize",
+            &[
+                &levelset_bind_group_layout,
+                &jump_flooding_seeds_bind_group_layout,
+            ],
+        );
+
+        let recompute_levelset_iteration_pipeline = queue(
+            "Queue RecomputeLevelsetIteratePipeline",
+            RECOMPUTE_LEVELSET_ITERATE_SHADER_HANDLE,
+            "iterate",
+            &[
+                &jump_flooding_seeds_bind_group_layout,
+                &jump_flooding_uniform_bind_group_layout,
+            ],
+        );
+
+        let recompute_levelset_solve_pipeline = queue(
+            "Queue RecomputeLevelsetSolvePipeline",
+            RECOMPUTE_LEVELSET_SDF_SHADER_HANDLE,
+            "calculate_sdf",
+            &[
+                &levelset_bind_group_layout,
+                &jump_flooding_seeds_bind_group_layout,
+            ],
+        );
+
+        let advect_levelset_pipeline = queue(
+            "Queue AdvectLevelsetPipeline",
+            ADVECT_LEVELSET_SHADER_HANDLE,
+            "advect_levelset",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_velocity_forward_pipeline = queue(
+            "Queue MacCormackVelocityForwardPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_velocity_forward",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_velocity_backward_pipeline = queue(
+            "Queue MacCormackVelocityBackwardPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_velocity_backward",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_velocity_correct_pipeline = queue(
+            "Queue MacCormackVelocityCorrectPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_velocity_correct",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_levelset_forward_pipeline = queue(
+            "Queue MacCormackLevelsetForwardPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_levelset_forward",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_levelset_backward_pipeline = queue(
+            "Queue MacCormackLevelsetBackwardPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_levelset_backward",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_levelset_correct_pipeline = queue(
+            "Queue MacCormackLevelsetCorrectPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_levelset_correct",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let mac_cormack_levelset_copy_pipeline = queue(
+            "Queue MacCormackLevelsetCopyPipeline",
+            MAC_CORMACK_SHADER_HANDLE,
+            "mac_cormack_levelset_copy",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &uniform_bind_group_layout,
+                &mac_cormack_bind_group_layout,
+            ],
+        );
+
+        let resample_velocity_pipeline = queue(
+            "Queue ResampleVelocityPipeline",
+            RESAMPLE_SHADER_HANDLE,
+            "resample_velocity",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let resample_levelset_pipeline = queue(
+            "Queue ResampleLevelsetPipeline",
+            RESAMPLE_SHADER_HANDLE,
+            "resample_levelset",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+            ],
+        );
+
+        let max_velocity_pipeline = queue(
+            "Queue MaxVelocityPipeline",
+            MAX_VELOCITY_SHADER_HANDLE,
+            "reduce_max_velocity",
+            &[&velocity_bind_group_layout, &readback_bind_group_layout],
+        );
+
+        let residual_pipeline = queue(
+            "Queue ResidualPipeline",
+            RESIDUAL_SHADER_HANDLE,
+            "reduce_residual",
+            &[
+                &velocity_bind_group_layout,
+                &levelset_bind_group_layout,
+                &readback_bind_group_layout,
+                &obstacles_bind_group_layout,
+            ],
+        );
 
         Self {
             initialize_velocity_pipeline,
             initialize_grid_center_pipeline,
+            clear_grid_center_pipeline,
             update_grid_label_pipeline,
+            update_face_fraction_pipeline,
             advection_pipeline,
             add_force_pipeline,
+            vorticity_pipeline,
+            vorticity_confinement_pipeline,
+            viscosity_copy_pipeline,
+            viscosity_explicit_pipeline,
+            viscosity_jacobi_pipeline,
+            viscosity_jacobi_reverse_pipeline,
             divergence_pipeline,
             jacobi_iteration_pipeline,
             jacobi_iteration_reverse_pipeline,
+            red_black_sor_red_pipeline,
+            red_black_sor_black_pipeline,
+            multigrid_restrict_finest_pipeline,
+            multigrid_restrict_coarse_pipeline,
+            multigrid_prolongate_pipeline,
+            multigrid_smooth_red_pipeline,
+            multigrid_smooth_black_pipeline,
+            pcg_initialize_pipeline,
+            pcg_precondition_jacobi_pipeline,
+            pcg_precondition_incomplete_poisson_upper_pipeline,
+            pcg_precondition_incomplete_poisson_lower_pipeline,
+            pcg_reduce_initial_pipeline,
+            pcg_update_direction_pipeline,
+            pcg_apply_pipeline,
+            pcg_reduce_alpha_pipeline,
+            pcg_update_pipeline,
+            pcg_reduce_beta_pipeline,
             solve_velocity_pipeline,
+            initialize_valid_faces_pipeline,
+            extrapolate_velocity_pipeline,
             recompute_levelset_initialization_pipeline,
             recompute_levelset_iteration_pipeline,
             recompute_levelset_solve_pipeline,
             advect_levelset_pipeline,
+            mac_cormack_velocity_forward_pipeline,
+            mac_cormack_velocity_backward_pipeline,
+            mac_cormack_velocity_correct_pipeline,
+            mac_cormack_levelset_forward_pipeline,
+            mac_cormack_levelset_backward_pipeline,
+            mac_cormack_levelset_correct_pipeline,
+            mac_cormack_levelset_copy_pipeline,
+            resample_velocity_pipeline,
+            resample_levelset_pipeline,
+            max_velocity_pipeline,
+            residual_pipeline,
             velocity_bind_group_layout,
             pressure_bind_group_layout,
             divergence_bind_group_layout,
@@ -318,6 +882,13 @@ impl FromWorld for FluidPipelines {
             obstacles_bind_group_layout,
             jump_flooding_uniform_bind_group_layout,
             jump_flooding_seeds_bind_group_layout,
+            readback_bind_group_layout,
+            multigrid_level_bind_group_layout,
+            pcg_bind_group_layout,
+            extrapolation_bind_group_layout,
+            mac_cormack_bind_group_layout,
+            vorticity_bind_group_layout,
+            viscosity_bind_group_layout,
         }
     }
 }
@@ -332,8 +903,93 @@ pub(crate) struct FluidBindGroups {
     pub jump_flooding_seeds_bind_group: BindGroup,
     pub uniform_bind_group: BindGroup,
     pub uniform_index: u32,
+    /// Size of the textures these bind groups refer to.
+    pub size: (u32, u32),
 }
 
+/// Bind groups of the textures which have been replaced by reallocation on size change.
+/// The bind groups keep the previous textures alive until their contents are resampled into the new textures.
+#[derive(Component)]
+pub(crate) struct FluidResampleBindGroups {
+    pub velocity_bind_group: BindGroup,
+    pub levelset_bind_group: BindGroup,
+}
+
+/// Buffers to read the reductions of a simulation back to the main world.
+/// `buffer` holds the maximum velocity, the residual of the pressure solve and the iterations of [`PressureSolver::Pcg`],
+/// written by the reductions on GPU, copied to `staging_buffer` and then mapped to be read on CPU.
+/// `state` is one of the `READBACK_*` constants and shared with the callback of the mapping.
+/// `request` records which reductions have been encoded for the values being read back.
+#[derive(Component)]
+pub(crate) struct FluidReadback {
+    pub buffer: Buffer,
+    pub staging_buffer: Buffer,
+    pub bind_group: BindGroup,
+    pub state: Arc<AtomicU8>,
+    pub request: Mutex<ReadbackRequest>,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ReadbackRequest {
+    pub max_velocity: bool,
+    /// Number of pressure iterations if the residual has been computed.
+    pub residual: Option<u32>,
+    /// The number of pressure iterations has been copied from [`PcgBindGroups::scalars`] on GPU.
+    pub pcg_iterations: bool,
+}
+
+/// `buffer` is ready for the next reduction.
+pub(crate) const READBACK_IDLE: u8 = 0;
+/// The reduction and the copy to `staging_buffer` have been encoded on this frame.
+pub(crate) const READBACK_COPIED: u8 = 1;
+/// Waiting for `staging_buffer` to be mapped.
+pub(crate) const READBACK_MAPPING: u8 = 2;
+/// `staging_buffer` is mapped and can be read.
+pub(crate) const READBACK_MAPPED: u8 = 3;
+
+/// Levels of [`PressureSolver::Multigrid`], from the finest to the coarsest.
+/// The finest level refers to `p1` of [`PressureTextures`], [`DivergenceTextures`] and the grid label of [`LevelsetTextures`],
+/// and the coarse levels to the textures owned by this component.
+/// Exists only in the render world, on the fluids solving pressure with multigrid.
+#[derive(Component)]
+pub(crate) struct MultigridBindGroups {
+    pub bind_groups: Vec<BindGroup>,
+    pub sizes: Vec<(u32, u32)>,
+    _textures: Vec<Texture>,
+}
+
+/// Sizes of the multigrid levels on a grid of `size`.
+/// The grid is halved while both sides are even and the shorter one stays at least 8 cells.
+fn multigrid_level_sizes(size: (u32, u32)) -> Vec<(u32, u32)> {
+    let mut sizes = vec![size];
+    let mut size = size;
+    while size.0 % 2 == 0 && size.1 % 2 == 0 && size.0.min(size.1) >= 16 {
+        size = (size.0 / 2, size.1 / 2);
+        sizes.push(size);
+    }
+    sizes
+}
+
+/// Vectors and scalars of [`PressureSolver::Pcg`], bound together with `p1` of [`PressureTextures`],
+/// [`DivergenceTextures`] and the grid label of [`LevelsetTextures`].
+/// Exists only in the render world, on the fluids solving pressure with PCG.
+#[derive(Component)]
+pub(crate) struct PcgBindGroups {
+    pub bind_group: BindGroup,
+    /// `PcgScalars` of pcg.wgsl. The number of iterations is at [`PCG_ITERATIONS_OFFSET`].
+    pub scalars: Buffer,
+    pub size: (u32, u32),
+    cells: Buffer,
+    partials: Buffer,
+}
+
+/// Byte offset of the number of iterations in `PcgScalars` of pcg.wgsl.
+pub(crate) const PCG_ITERATIONS_OFFSET: u64 = 16;
+/// Size of `PcgScalars` of pcg.wgsl.
+const PCG_SCALARS_SIZE: u64 = 24;
+/// Size of `PcgCell` of pcg.wgsl.
+const PCG_CELL_SIZE: u64 = 20;
+
 #[derive(Resource)]
 pub(crate) struct FluidBindGroupResources {
     pub obstacles_bind_group: BindGroup,
@@ -353,12 +1009,16 @@ pub(super) fn prepare_resource_recompute_levelset(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     render_queue: Res<RenderQueue>,
-    query: Query<(Entity, &FluidSettings)>,
+    query: Query<(Entity, &FluidSettings, Option<&JumpFloodingUniformBuffer>)>,
 ) {
-    for (entity, settings) in &query {
+    for (entity, settings, jump_flooding_uniform_buffer) in &query {
         // steps for jump flooding algorithm: 1, 2, ..., 2^k, where: 2^k < max(size.0, size.1) <= 2^(k+1)
         let max_power =
             ((settings.size.0.max(settings.size.1) as f32).log2() - 1.0).floor() as usize;
+        // The buffers are kept until the size is changed and dropped together with the entity.
+        if jump_flooding_uniform_buffer.is_some_and(|buffer| buffer.buffer.len() == max_power + 1) {
+            continue;
+        }
         let mut step = 2_u32.pow((max_power + 1) as u32);
         let mut jump_flooding_buffer =
             Vec::<UniformBuffer<JumpFloodingUniform>>::with_capacity(max_power + 1);
@@ -376,29 +1036,97 @@ pub(super) fn prepare_resource_recompute_levelset(
     }
 }
 
+type FluidTexturesQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static MainEntity,
+        Option<&'static FluidBindGroups>,
+        &'static VelocityTextures,
+        &'static PressureTextures,
+        &'static DivergenceTextures,
+        &'static LevelsetTextures,
+        &'static LocalForces,
+        &'static DynamicUniformIndex<SimulationUniform>,
+        &'static JumpFloodingSeedsTextures,
+        &'static JumpFloodingUniformBuffer,
+    ),
+>;
+
+/// Failures of creating bind groups in a prepare system, reported as [`FluidError`] once per entity and resource
+/// until the bind group is created again, so that a persistent failure does not flood the events on every frame.
+pub(super) struct BindGroupErrors {
+    reported: HashSet<(Option<Entity>, &'static str)>,
+    errors: Vec<FluidError>,
+    queue: FluidErrorQueue,
+}
+
+impl FromWorld for BindGroupErrors {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            reported: HashSet::default(),
+            errors: Vec::new(),
+            queue: world.resource::<FluidErrorQueue>().clone(),
+        }
+    }
+}
+
+impl BindGroupErrors {
+    /// Sends the failures reported since the last flush.
+    fn flush(&mut self) {
+        if !self.errors.is_empty() {
+            self.queue.0.lock().unwrap().append(&mut self.errors);
+        }
+    }
+}
+
+/// Creates a bind group of `resource`, reporting the failure as [`FluidError`] on `entity`.
+/// [`AsBindGroupError::RetryNextUpdate`] is not reported since it is expected while the images are being uploaded.
+fn fluid_bind_group<T: AsBindGroup>(
+    resource: &T,
+    layout: &BindGroupLayout,
+    render_device: &RenderDevice,
+    param: &mut SystemParamItem<T::Param>,
+    entity: Option<Entity>,
+    errors: &mut BindGroupErrors,
+) -> Option<BindGroup> {
+    let key = (entity, std::any::type_name::<T>());
+    match resource.as_bind_group(layout, render_device, param) {
+        Ok(prepared) => {
+            errors.reported.remove(&key);
+            Some(prepared.bind_group)
+        }
+        Err(AsBindGroupError::RetryNextUpdate) => None,
+        Err(err) => {
+            if errors.reported.insert(key) {
+                errors.errors.push(FluidError {
+                    entity,
+                    stage: FluidErrorStage::PrepareBindGroups,
+                    message: format!("{}: {err}", key.1),
+                });
+            }
+            None
+        }
+    }
+}
+
+/// Bind groups of an entity are created only if all of them can be created.
+/// Otherwise, the entity is skipped on this frame and the failure is sent as [`FluidError`] once, see [`BindGroupErrors`].
 pub(super) fn prepare_fluid_bind_groups(
     mut commands: Commands,
     pipelines: Res<FluidPipelines>,
     simulation_uniform: Res<ComponentUniforms<SimulationUniform>>,
-    query: Query<(
-        Entity,
-        &VelocityTextures,
-        &PressureTextures,
-        &DivergenceTextures,
-        &LevelsetTextures,
-        &LocalForces,
-        &DynamicUniformIndex<SimulationUniform>,
-        &JumpFloodingSeedsTextures,
-        &JumpFloodingUniformBuffer,
-    )>,
+    query: FluidTexturesQuery,
     render_device: Res<RenderDevice>,
-    gpu_images: Res<RenderAssets<GpuImage>>,
-    fallback_image: Res<FallbackImage>,
-    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    param: StaticSystemParam<<VelocityTextures as AsBindGroup>::Param>,
+    mut errors: Local<BindGroupErrors>,
 ) {
-    let mut param = (gpu_images, fallback_image, buffers);
+    let mut param = param.into_inner();
     for (
         entity,
+        main_entity,
+        prev_bind_groups,
         velocity_textures,
         pressure_textures,
         divergence_textures,
@@ -409,76 +1137,120 @@ pub(super) fn prepare_fluid_bind_groups(
         jump_flooding_uniform_buffer,
     ) in &query
     {
-        let simulation_uniform = simulation_uniform.uniforms();
+        // Textures can be reallocated a frame later than FluidSettings is changed,
+        // so the size is taken from the textures themselves.
+        let Some(levelset) = param.0.get(&levelset_textures.levelset) else {
+            continue;
+        };
+        let size = (levelset.size.x, levelset.size.y);
+        let main_entity = Some(main_entity.id());
+
+        let Some(simulation_uniform) = simulation_uniform.uniforms().binding() else {
+            continue;
+        };
         let uniform_bind_group = render_device.create_bind_group(
             "Simulation Uniform BindGroup",
             &pipelines.uniform_bind_group_layout,
             &BindGroupEntries::single(simulation_uniform),
         );
 
-        let velocity_bind_group = velocity_textures
-            .as_bind_group(
-                &pipelines.velocity_bind_group_layout,
-                &render_device,
-                &mut param,
-            )
-            .unwrap()
-            .bind_group;
-
-        let pressure_bind_group = pressure_textures
-            .as_bind_group(
-                &pipelines.pressure_bind_group_layout,
-                &render_device,
-                &mut param,
-            )
-            .unwrap()
-            .bind_group;
-
-        let divergence_bind_group = divergence_textures
-            .as_bind_group(
-                &pipelines.divergence_bind_group_layout,
-                &render_device,
-                &mut param,
-            )
-            .unwrap()
-            .bind_group;
-
-        let local_forces_bind_group = local_forces
-            .as_bind_group(
-                &pipelines.local_forces_bind_group_layout,
-                &render_device,
-                &mut param,
-            )
-            .unwrap()
-            .bind_group;
-
-        let mut jump_flooding_step_bind_groups =
-            Vec::with_capacity(jump_flooding_uniform_buffer.buffer.len());
-        for buffer in &jump_flooding_uniform_buffer.buffer {
-            jump_flooding_step_bind_groups.push(render_device.create_bind_group(
-                Some("Create JumpFloodingStepBindGroup"),
-                &pipelines.jump_flooding_uniform_bind_group_layout,
-                &BindGroupEntries::single(buffer.binding().unwrap()),
-            ));
-        }
+        let velocity_bind_group = fluid_bind_group(
+            velocity_textures,
+            &pipelines.velocity_bind_group_layout,
+            &render_device,
+            &mut param,
+            main_entity,
+            &mut errors,
+        );
 
-        let levelset_bind_group = levelset_textures
-            .as_bind_group(
-                &pipelines.levelset_bind_group_layout,
-                &render_device,
-                &mut param,
-            )
-            .unwrap()
-            .bind_group;
-
-        let jump_flooding_seeds_bind_group = jump_flooding_seeds_textures
-            .as_bind_group(
-                &pipelines.jump_flooding_seeds_bind_group_layout,
-                &render_device,
-                &mut param,
-            )
-            .unwrap()
-            .bind_group;
+        let pressure_bind_group = fluid_bind_group(
+            pressure_textures,
+            &pipelines.pressure_bind_group_layout,
+            &render_device,
+            &mut param,
+            main_entity,
+            &mut errors,
+        );
+
+        let divergence_bind_group = fluid_bind_group(
+            divergence_textures,
+            &pipelines.divergence_bind_group_layout,
+            &render_device,
+            &mut param,
+            main_entity,
+            &mut errors,
+        );
+
+        let local_forces_bind_group = fluid_bind_group(
+            local_forces,
+            &pipelines.local_forces_bind_group_layout,
+            &render_device,
+            &mut param,
+            main_entity,
+            &mut errors,
+        );
+
+        let levelset_bind_group = fluid_bind_group(
+            levelset_textures,
+            &pipelines.levelset_bind_group_layout,
+            &render_device,
+            &mut param,
+            main_entity,
+            &mut errors,
+        );
+
+        let jump_flooding_seeds_bind_group = fluid_bind_group(
+            jump_flooding_seeds_textures,
+            &pipelines.jump_flooding_seeds_bind_group_layout,
+            &render_device,
+            &mut param,
+            main_entity,
+            &mut errors,
+        );
+
+        let (
+            Some(velocity_bind_group),
+            Some(pressure_bind_group),
+            Some(divergence_bind_group),
+            Some(local_forces_bind_group),
+            Some(levelset_bind_group),
+            Some(jump_flooding_seeds_bind_group),
+        ) = (
+            velocity_bind_group,
+            pressure_bind_group,
+            divergence_bind_group,
+            local_forces_bind_group,
+            levelset_bind_group,
+            jump_flooding_seeds_bind_group,
+        )
+        else {
+            continue;
+        };
+
+        // The buffers are written in RenderSet::PrepareResources.
+        let Some(jump_flooding_step_bind_groups) = jump_flooding_uniform_buffer
+            .buffer
+            .iter()
+            .map(|buffer| {
+                Some(render_device.create_bind_group(
+                    Some("Create JumpFloodingStepBindGroup"),
+                    &pipelines.jump_flooding_uniform_bind_group_layout,
+                    &BindGroupEntries::single(buffer.binding()?),
+                ))
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+
+        if let Some(prev_bind_groups) = prev_bind_groups {
+            if prev_bind_groups.size != size {
+                commands.entity(entity).insert(FluidResampleBindGroups {
+                    velocity_bind_group: prev_bind_groups.velocity_bind_group.clone(),
+                    levelset_bind_group: prev_bind_groups.levelset_bind_group.clone(),
+                });
+            }
+        }
 
         commands.entity(entity).insert((
             FluidBindGroups {
@@ -490,12 +1262,15 @@ pub(super) fn prepare_fluid_bind_groups(
                 jump_flooding_seeds_bind_group,
                 uniform_bind_group,
                 uniform_index: simulation_uniform_index.index(),
+                size,
             },
             JumpFloodingUniformBindGroups {
                 jump_flooding_step_bind_groups: jump_flooding_step_bind_groups.into_boxed_slice(),
             },
         ));
     }
+
+    errors.flush();
 }
 
 pub(super) fn prepare_fluid_bind_group_for_resources(
@@ -503,20 +1278,596 @@ pub(super) fn prepare_fluid_bind_group_for_resources(
     pilelines: Res<FluidPipelines>,
     obstacles: Res<Obstacles>,
     render_device: Res<RenderDevice>,
-    gpu_images: Res<RenderAssets<GpuImage>>,
-    fallback_image: Res<FallbackImage>,
-    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    param: StaticSystemParam<<Obstacles as AsBindGroup>::Param>,
+    mut errors: Local<BindGroupErrors>,
 ) {
-    let mut param = (gpu_images, fallback_image, buffers);
-    let obstacles_bind_group = obstacles
-        .as_bind_group(
-            &pilelines.obstacles_bind_group_layout,
-            &render_device,
-            &mut param,
-        )
-        .unwrap()
-        .bind_group;
+    let obstacles_bind_group = fluid_bind_group(
+        &*obstacles,
+        &pilelines.obstacles_bind_group_layout,
+        &render_device,
+        &mut param.into_inner(),
+        None,
+        &mut errors,
+    );
+    errors.flush();
+    let Some(obstacles_bind_group) = obstacles_bind_group else {
+        return;
+    };
     commands.insert_resource(FluidBindGroupResources {
         obstacles_bind_group,
     });
 }
+
+/// Buffers for the maximum velocity are created once per entity as they do not depend on the size.
+pub(super) fn prepare_fluid_readback(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    render_device: Res<RenderDevice>,
+    query: Query<Entity, (With<FluidSettings>, Without<FluidReadback>)>,
+) {
+    for entity in &query {
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("FluidReadbackBuffer"),
+            size: size_of::<[u32; 3]>() as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("FluidReadbackStagingBuffer"),
+            size: size_of::<[u32; 3]>() as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = render_device.create_bind_group(
+            Some("Create ReadbackBindGroup"),
+            &pipelines.readback_bind_group_layout,
+            &BindGroupEntries::single(buffer.as_entire_binding()),
+        );
+        commands.entity(entity).insert(FluidReadback {
+            buffer,
+            staging_buffer,
+            bind_group,
+            state: Arc::new(AtomicU8::new(READBACK_IDLE)),
+            request: Mutex::new(ReadbackRequest::default()),
+        });
+    }
+}
+
+/// Creates the textures of the coarse levels when a fluid starts solving pressure with [`PressureSolver::Multigrid`]
+/// or its size changes, and removes them when it switches to another solver.
+/// The bind group of the finest level is created on every frame like [`FluidBindGroups`].
+pub(super) fn prepare_multigrid_bind_groups(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    mut query: Query<MultigridQueryData>,
+) {
+    for (
+        entity,
+        pressure_textures,
+        divergence_textures,
+        levelset_textures,
+        solver_settings,
+        multigrid,
+    ) in &mut query
+    {
+        let solver_settings = &solver_settings.0;
+        if !matches!(
+            solver_settings.pressure_solver,
+            PressureSolver::Multigrid { .. }
+        ) {
+            if multigrid.is_some() {
+                commands.entity(entity).remove::<MultigridBindGroups>();
+            }
+            continue;
+        }
+
+        let (Some(p1), Some(div), Some(grid_label)) = (
+            gpu_images.get(&pressure_textures.p1),
+            gpu_images.get(&divergence_textures.div),
+            gpu_images.get(&levelset_textures.grid_label),
+        ) else {
+            continue;
+        };
+        let size = (grid_label.size.x, grid_label.size.y);
+        let finest_bind_group = render_device.create_bind_group(
+            Some("Create MultigridLevelBindGroup"),
+            &pipelines.multigrid_level_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &p1.texture_view,
+                &div.texture_view,
+                &grid_label.texture_view,
+            )),
+        );
+
+        match multigrid {
+            Some(mut multigrid) if multigrid.sizes[0] == size => {
+                multigrid.bind_groups[0] = finest_bind_group;
+            }
+            _ => {
+                let sizes = multigrid_level_sizes(size);
+                let mut bind_groups = vec![finest_bind_group];
+                let mut textures = Vec::new();
+                for &(width, height) in &sizes[1..] {
+                    let level_textures = [
+                        TextureFormat::R32Float,
+                        TextureFormat::R32Float,
+                        TextureFormat::R32Uint,
+                    ]
+                    .map(|format| {
+                        render_device.create_texture(&TextureDescriptor {
+                            label: Some("MultigridLevelTexture"),
+                            size: Extent3d {
+                                width,
+                                height,
+                                depth_or_array_layers: 1,
+                            },
+                            mip_level_count: 1,
+                            sample_count: 1,
+                            dimension: TextureDimension::D2,
+                            format,
+                            usage: TextureUsages::STORAGE_BINDING,
+                            view_formats: &[],
+                        })
+                    });
+                    let [p, rhs, label] = level_textures
+                        .each_ref()
+                        .map(|texture| texture.create_view(&default()));
+                    bind_groups.push(render_device.create_bind_group(
+                        Some("Create MultigridLevelBindGroup"),
+                        &pipelines.multigrid_level_bind_group_layout,
+                        &BindGroupEntries::sequential((&p, &rhs, &label)),
+                    ));
+                    textures.extend(level_textures);
+                }
+                commands.entity(entity).insert(MultigridBindGroups {
+                    bind_groups,
+                    sizes,
+                    _textures: textures,
+                });
+            }
+        }
+    }
+}
+
+/// Valid flags of the faces for the velocity extrapolation, ping-ponged between the layers.
+/// `bind_groups[0]` reads the first pair of textures and writes the second, and `bind_groups[1]` the other way around.
+/// Exists only in the render world, on the fluids with non-zero [`FluidSolverSettings::extrapolation_layers`](super::definition::FluidSolverSettings::extrapolation_layers).
+#[derive(Component)]
+pub(crate) struct ExtrapolationBindGroups {
+    pub bind_groups: [BindGroup; 2],
+    size: (u32, u32),
+    _textures: [Texture; 4],
+}
+
+/// Creates the textures of the velocity extrapolation when a fluid enables it or its size changes,
+/// and removes them when it is disabled.
+pub(super) fn prepare_extrapolation_bind_groups(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    query: Query<ExtrapolationQueryData>,
+) {
+    for (entity, levelset_textures, solver_settings, extrapolation) in &query {
+        let solver_settings = &solver_settings.0;
+        if solver_settings.extrapolation_layers == 0 {
+            if extrapolation.is_some() {
+                commands.entity(entity).remove::<ExtrapolationBindGroups>();
+            }
+            continue;
+        }
+
+        let Some(grid_label) = gpu_images.get(&levelset_textures.grid_label) else {
+            continue;
+        };
+        let size = (grid_label.size.x, grid_label.size.y);
+        if extrapolation.is_some_and(|extrapolation| extrapolation.size == size) {
+            continue;
+        }
+
+        let textures = [
+            (size.0 + 1, size.1),
+            (size.0, size.1 + 1),
+            (size.0 + 1, size.1),
+            (size.0, size.1 + 1),
+        ]
+        .map(|(width, height)| {
+            render_device.create_texture(&TextureDescriptor {
+                label: Some("ExtrapolationValidTexture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::R32Uint,
+                usage: TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            })
+        });
+        let [u_valid0, v_valid0, u_valid1, v_valid1] = textures
+            .each_ref()
+            .map(|texture| texture.create_view(&default()));
+        let bind_groups = [
+            (&u_valid0, &v_valid0, &u_valid1, &v_valid1),
+            (&u_valid1, &v_valid1, &u_valid0, &v_valid0),
+        ]
+        .map(|views| {
+            render_device.create_bind_group(
+                Some("Create ExtrapolationBindGroup"),
+                &pipelines.extrapolation_bind_group_layout,
+                &BindGroupEntries::sequential(views),
+            )
+        });
+        commands.entity(entity).insert(ExtrapolationBindGroups {
+            bind_groups,
+            size,
+            _textures: textures,
+        });
+    }
+}
+
+/// Intermediate fields of [`AdvectionScheme::MacCormack`]: the velocity advected backward,
+/// and the levelset advected forward and backward.
+/// Exists only in the render world, on the fluids advected with MacCormack.
+#[derive(Component)]
+pub(crate) struct MacCormackBindGroups {
+    pub bind_group: BindGroup,
+    size: (u32, u32),
+    _textures: [Texture; 4],
+}
+
+/// Creates the textures of [`AdvectionScheme::MacCormack`] when a fluid starts advecting with it
+/// or its size changes, and removes them when it switches back.
+pub(super) fn prepare_mac_cormack_bind_groups(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    query: Query<MacCormackQueryData>,
+) {
+    for (entity, levelset_textures, solver_settings, mac_cormack) in &query {
+        let solver_settings = &solver_settings.0;
+        if solver_settings.advection_scheme != AdvectionScheme::MacCormack {
+            if mac_cormack.is_some() {
+                commands.entity(entity).remove::<MacCormackBindGroups>();
+            }
+            continue;
+        }
+
+        let Some(levelset) = gpu_images.get(&levelset_textures.levelset) else {
+            continue;
+        };
+        let size = (levelset.size.x, levelset.size.y);
+        if mac_cormack.is_some_and(|mac_cormack| mac_cormack.size == size) {
+            continue;
+        }
+
+        let textures =
+            [(size.0 + 1, size.1), (size.0, size.1 + 1), size, size].map(|(width, height)| {
+                render_device.create_texture(&TextureDescriptor {
+                    label: Some("MacCormackTexture"),
+                    size: Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::R32Float,
+                    usage: TextureUsages::STORAGE_BINDING,
+                    view_formats: &[],
+                })
+            });
+        let [u_backward, v_backward, levelset_forward, levelset_backward] = textures
+            .each_ref()
+            .map(|texture| texture.create_view(&default()));
+        let bind_group = render_device.create_bind_group(
+            Some("Create MacCormackBindGroup"),
+            &pipelines.mac_cormack_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &u_backward,
+                &v_backward,
+                &levelset_forward,
+                &levelset_backward,
+            )),
+        );
+        commands.entity(entity).insert(MacCormackBindGroups {
+            bind_group,
+            size,
+            _textures: textures,
+        });
+    }
+}
+
+/// Curl of the advected velocity on the cell centers, used by vorticity confinement.
+/// Exists only in the render world, on the fluids with non-zero [`FluidSolverSettings::vorticity_confinement`](super::definition::FluidSolverSettings::vorticity_confinement).
+#[derive(Component)]
+pub(crate) struct VorticityBindGroups {
+    pub bind_group: BindGroup,
+    size: (u32, u32),
+    _texture: Texture,
+}
+
+/// Creates the vorticity texture when a fluid enables vorticity confinement or its size changes,
+/// and removes it when vorticity confinement is disabled.
+pub(super) fn prepare_vorticity_bind_groups(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    query: Query<VorticityQueryData>,
+) {
+    for (entity, levelset_textures, solver_settings, vorticity) in &query {
+        let solver_settings = &solver_settings.0;
+        if solver_settings.vorticity_confinement == 0.0 {
+            if vorticity.is_some() {
+                commands.entity(entity).remove::<VorticityBindGroups>();
+            }
+            continue;
+        }
+
+        let Some(levelset) = gpu_images.get(&levelset_textures.levelset) else {
+            continue;
+        };
+        let size = (levelset.size.x, levelset.size.y);
+        if vorticity.is_some_and(|vorticity| vorticity.size == size) {
+            continue;
+        }
+
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("VorticityTexture"),
+            size: Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R32Float,
+            usage: TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+        let bind_group = render_device.create_bind_group(
+            Some("Create VorticityBindGroup"),
+            &pipelines.vorticity_bind_group_layout,
+            &BindGroupEntries::single(&texture.create_view(&default())),
+        );
+        commands.entity(entity).insert(VorticityBindGroups {
+            bind_group,
+            size,
+            _texture: texture,
+        });
+    }
+}
+
+/// Velocity before the viscous diffusion and the intermediate velocity of
+/// [`ViscositySolver::Implicit`](super::definition::ViscositySolver::Implicit).
+/// Exists only in the render world, on the fluids with positive [`FluidSettings::viscosity`].
+#[derive(Component)]
+pub(crate) struct ViscosityBindGroups {
+    pub bind_group: BindGroup,
+    size: (u32, u32),
+    _textures: [Texture; 4],
+}
+
+/// Creates the textures of the viscous diffusion when a fluid becomes viscous or its size changes,
+/// and removes them when its viscosity is set back to zero.
+pub(super) fn prepare_viscosity_bind_groups(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    query: Query<ViscosityQueryData>,
+) {
+    for (entity, levelset_textures, settings, viscosity) in &query {
+        if settings.viscosity <= 0.0 {
+            if viscosity.is_some() {
+                commands.entity(entity).remove::<ViscosityBindGroups>();
+            }
+            continue;
+        }
+
+        let Some(levelset) = gpu_images.get(&levelset_textures.levelset) else {
+            continue;
+        };
+        let size = (levelset.size.x, levelset.size.y);
+        if viscosity.is_some_and(|viscosity| viscosity.size == size) {
+            continue;
+        }
+
+        let textures = [
+            (size.0 + 1, size.1),
+            (size.0, size.1 + 1),
+            (size.0 + 1, size.1),
+            (size.0, size.1 + 1),
+        ]
+        .map(|(width, height)| {
+            render_device.create_texture(&TextureDescriptor {
+                label: Some("ViscosityTexture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::R32Float,
+                usage: TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            })
+        });
+        let [u_old, v_old, u_tmp, v_tmp] = textures
+            .each_ref()
+            .map(|texture| texture.create_view(&default()));
+        let bind_group = render_device.create_bind_group(
+            Some("Create ViscosityBindGroup"),
+            &pipelines.viscosity_bind_group_layout,
+            &BindGroupEntries::sequential((&u_old, &v_old, &u_tmp, &v_tmp)),
+        );
+        commands.entity(entity).insert(ViscosityBindGroups {
+            bind_group,
+            size,
+            _textures: textures,
+        });
+    }
+}
+
+/// Creates the buffers of [`PressureSolver::Pcg`] when a fluid starts solving pressure with it
+/// or its size changes, and removes them when it switches to another solver.
+/// The bind group is created on every frame like [`FluidBindGroups`].
+pub(super) fn prepare_pcg_bind_groups(
+    mut commands: Commands,
+    pipelines: Res<FluidPipelines>,
+    config: Res<FluidConfig>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    query: Query<PcgQueryData>,
+) {
+    for (entity, pressure_textures, divergence_textures, levelset_textures, solver_settings, pcg) in
+        &query
+    {
+        let solver_settings = &solver_settings.0;
+        if !matches!(solver_settings.pressure_solver, PressureSolver::Pcg { .. }) {
+            if pcg.is_some() {
+                commands.entity(entity).remove::<PcgBindGroups>();
+            }
+            continue;
+        }
+
+        let (
+            Some(p1),
+            Some(div),
+            Some(grid_label),
+            Some(levelset),
+            Some(u_fraction),
+            Some(v_fraction),
+        ) = (
+            gpu_images.get(&pressure_textures.p1),
+            gpu_images.get(&divergence_textures.div),
+            gpu_images.get(&levelset_textures.grid_label),
+            gpu_images.get(&levelset_textures.levelset),
+            gpu_images.get(&levelset_textures.u_fraction),
+            gpu_images.get(&levelset_textures.v_fraction),
+        )
+        else {
+            continue;
+        };
+        let size = (grid_label.size.x, grid_label.size.y);
+        let (cells, partials, scalars) = match pcg {
+            Some(pcg) if pcg.size == size => {
+                (pcg.cells.clone(), pcg.partials.clone(), pcg.scalars.clone())
+            }
+            _ => {
+                let workgroups =
+                    size.0.div_ceil(config.workgroup_size) * size.1.div_ceil(config.workgroup_size);
+                let buffer = |label, size, usage| {
+                    render_device.create_buffer(&BufferDescriptor {
+                        label: Some(label),
+                        size,
+                        usage: BufferUsages::STORAGE | usage,
+                        mapped_at_creation: false,
+                    })
+                };
+                (
+                    buffer(
+                        "PcgCellBuffer",
+                        PCG_CELL_SIZE * (size.0 * size.1) as u64,
+                        BufferUsages::empty(),
+                    ),
+                    buffer(
+                        "PcgPartialBuffer",
+                        size_of::<[f32; 2]>() as u64 * workgroups as u64,
+                        BufferUsages::empty(),
+                    ),
+                    buffer("PcgScalarBuffer", PCG_SCALARS_SIZE, BufferUsages::COPY_SRC),
+                )
+            }
+        };
+        let bind_group = render_device.create_bind_group(
+            Some("Create PcgBindGroup"),
+            &pipelines.pcg_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &p1.texture_view,
+                &div.texture_view,
+                &grid_label.texture_view,
+                cells.as_entire_binding(),
+                partials.as_entire_binding(),
+                scalars.as_entire_binding(),
+                &levelset.texture_view,
+                &u_fraction.texture_view,
+                &v_fraction.texture_view,
+            )),
+        );
+        commands.entity(entity).insert(PcgBindGroups {
+            bind_group,
+            scalars,
+            size,
+            cells,
+            partials,
+        });
+    }
+}
+
+type PcgQueryData<'a> = (
+    Entity,
+    &'a PressureTextures,
+    &'a DivergenceTextures,
+    &'a LevelsetTextures,
+    &'a ResolvedSolverSettings,
+    Option<&'a PcgBindGroups>,
+);
+
+type MultigridQueryData<'a> = (
+    Entity,
+    &'a PressureTextures,
+    &'a DivergenceTextures,
+    &'a LevelsetTextures,
+    &'a ResolvedSolverSettings,
+    Option<&'a mut MultigridBindGroups>,
+);
+
+type MacCormackQueryData<'a> = (
+    Entity,
+    &'a LevelsetTextures,
+    &'a ResolvedSolverSettings,
+    Option<&'a MacCormackBindGroups>,
+);
+
+type VorticityQueryData<'a> = (
+    Entity,
+    &'a LevelsetTextures,
+    &'a ResolvedSolverSettings,
+    Option<&'a VorticityBindGroups>,
+);
+
+type ViscosityQueryData<'a> = (
+    Entity,
+    &'a LevelsetTextures,
+    &'a FluidSettings,
+    Option<&'a ViscosityBindGroups>,
+);
+
+type ExtrapolationQueryData<'a> = (
+    Entity,
+    &'a LevelsetTextures,
+    &'a ResolvedSolverSettings,
+    Option<&'a ExtrapolationBindGroups>,
+);
+
+/// [`FluidResampleBindGroups`] is consumed by the render node on the frame it is inserted.
+pub(super) fn cleanup_resample_bind_groups(
+    mut commands: Commands,
+    query: Query<Entity, With<FluidResampleBindGroups>>,
+) {
+    for entity in &query {
+        commands.entity(entity).remove::<FluidResampleBindGroups>();
+    }
+}
@@ -1,106 +1,566 @@
+use std::sync::{atomic::Ordering, Arc, Mutex};
+
 use bevy::{
     prelude::*,
     render::{
+        extract_component::DynamicUniformIndex,
         render_graph::{self, RenderLabel},
-        render_resource::{CachedPipelineState, ComputePassDescriptor, PipelineCache},
+        render_resource::{
+            CachedComputePipelineId, CachedPipelineState, ComputePass, ComputePassDescriptor,
+            ComputePipeline, Maintain, MapMode, PipelineCache, PipelineCacheError,
+        },
+        renderer::RenderDevice,
+        sync_world::{MainEntity, RenderEntity},
+        Extract,
     },
+    utils::HashSet,
 };
 
 use super::{
-    definition::FluidSettings,
+    definition::{
+        DivergenceTextures, FluidConfig, FluidError, FluidErrorStage, FluidSettings,
+        JumpFloodingSeedsTextures, JumpFloodingUniformBuffer, LevelsetTextures, LocalForces,
+        PcgPreconditioner, PressureSolver, PressureTextures, ResetFluid, SimulationUniform,
+        VelocityTextures, ViscositySolver, MULTIGRID_COARSEST_SMOOTHING,
+    },
     fluid_bind_group::{
-        FluidBindGroupResources, FluidBindGroups, FluidPipelines, JumpFloodingUniformBindGroups,
+        ExtrapolationBindGroups, FluidBindGroupResources, FluidBindGroups, FluidPipelines,
+        FluidReadback, FluidResampleBindGroups, JumpFloodingUniformBindGroups,
+        MacCormackBindGroups, MultigridBindGroups, PcgBindGroups, ReadbackRequest,
+        ViscosityBindGroups, VorticityBindGroups, PCG_ITERATIONS_OFFSET, READBACK_COPIED,
+        READBACK_IDLE, READBACK_MAPPED, READBACK_MAPPING,
     },
+    setup_components::{PressureIterations, ResolvedSolverSettings, SimulationSteps},
 };
 
-const WORKGROUP_SIZE: u32 = 8;
-
+/// Label of the render graph node which runs the simulation.
+/// The node runs before [`CameraDriverLabel`](bevy::render::graph::CameraDriverLabel),
+/// so the simulation results of the frame can be rendered on the same frame.
+/// Add node edges to order own compute nodes against the simulation.
+/// # Examples
+/// ```rust
+/// use bevy::{
+///     prelude::*,
+///     render::{
+///         render_graph::{RenderGraph, RenderLabel},
+///         RenderApp,
+///     },
+/// };
+/// use bevy_eulerian_fluid::render_node::FluidLabel;
+///
+/// #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+/// struct EmitterLabel;
+///
+/// fn build(app: &mut App) {
+///     let render_app = app.sub_app_mut(RenderApp);
+///     let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+///     // Once a node is added with EmitterLabel, run it before the simulation.
+///     render_graph.add_node_edge(EmitterLabel, FluidLabel);
+/// }
+/// ```
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
-pub(crate) struct FluidLabel;
+pub struct FluidLabel;
 
 enum State {
     Loading,
-    Init,
-    Update,
+    Ready,
+    /// A pipeline failed to compile. The error has been reported with [`FluidError`],
+    /// and the node waits until the pipeline is recompiled, e.g. by hot reloading the shader.
+    Failed,
+}
+
+/// Per-entity lifecycle of a simulation in the render world.
+/// An entity becomes [`FluidSimulationState::Initializing`] on the first frame its [`FluidBindGroups`] are available,
+/// runs the initialization passes exactly once, and then stays [`FluidSimulationState::Running`].
+/// It only becomes running on the frame after the initialization passes have actually been dispatched.
+/// Removing the state, e.g. on [`ResetFluid`], lets the entity go through the initialization again.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FluidSimulationState {
+    Initializing,
+    Running,
+}
+
+/// Main world entities whose initialization has been submitted by [`EulerFluidNode`].
+/// Shared between the main world and the render world, and drained on the main world to send [`FluidInitialized`](super::definition::FluidInitialized).
+#[derive(Resource, Clone, Default)]
+pub(crate) struct FluidInitializedQueue(pub Arc<Mutex<Vec<Entity>>>);
+
+/// Errors occurred on the render world.
+/// Shared between the main world and the render world, and drained on the main world to send [`FluidError`].
+#[derive(Resource, Clone, Default)]
+pub(crate) struct FluidErrorQueue(pub Arc<Mutex<Vec<FluidError>>>);
+
+fn pipeline_ids(pipelines: &FluidPipelines) -> [CachedComputePipelineId; 51] {
+    [
+        pipelines.initialize_velocity_pipeline,
+        pipelines.initialize_grid_center_pipeline,
+        pipelines.clear_grid_center_pipeline,
+        pipelines.update_grid_label_pipeline,
+        pipelines.update_face_fraction_pipeline,
+        pipelines.advection_pipeline,
+        pipelines.add_force_pipeline,
+        pipelines.vorticity_pipeline,
+        pipelines.vorticity_confinement_pipeline,
+        pipelines.viscosity_copy_pipeline,
+        pipelines.viscosity_explicit_pipeline,
+        pipelines.viscosity_jacobi_pipeline,
+        pipelines.viscosity_jacobi_reverse_pipeline,
+        pipelines.divergence_pipeline,
+        pipelines.jacobi_iteration_pipeline,
+        pipelines.jacobi_iteration_reverse_pipeline,
+        pipelines.red_black_sor_red_pipeline,
+        pipelines.red_black_sor_black_pipeline,
+        pipelines.multigrid_restrict_finest_pipeline,
+        pipelines.multigrid_restrict_coarse_pipeline,
+        pipelines.multigrid_prolongate_pipeline,
+        pipelines.multigrid_smooth_red_pipeline,
+        pipelines.multigrid_smooth_black_pipeline,
+        pipelines.pcg_initialize_pipeline,
+        pipelines.pcg_precondition_jacobi_pipeline,
+        pipelines.pcg_precondition_incomplete_poisson_upper_pipeline,
+        pipelines.pcg_precondition_incomplete_poisson_lower_pipeline,
+        pipelines.pcg_reduce_initial_pipeline,
+        pipelines.pcg_update_direction_pipeline,
+        pipelines.pcg_apply_pipeline,
+        pipelines.pcg_reduce_alpha_pipeline,
+        pipelines.pcg_update_pipeline,
+        pipelines.pcg_reduce_beta_pipeline,
+        pipelines.solve_velocity_pipeline,
+        pipelines.initialize_valid_faces_pipeline,
+        pipelines.extrapolate_velocity_pipeline,
+        pipelines.recompute_levelset_initialization_pipeline,
+        pipelines.recompute_levelset_iteration_pipeline,
+        pipelines.recompute_levelset_solve_pipeline,
+        pipelines.advect_levelset_pipeline,
+        pipelines.mac_cormack_velocity_forward_pipeline,
+        pipelines.mac_cormack_velocity_backward_pipeline,
+        pipelines.mac_cormack_velocity_correct_pipeline,
+        pipelines.mac_cormack_levelset_forward_pipeline,
+        pipelines.mac_cormack_levelset_backward_pipeline,
+        pipelines.mac_cormack_levelset_correct_pipeline,
+        pipelines.mac_cormack_levelset_copy_pipeline,
+        pipelines.resample_velocity_pipeline,
+        pipelines.resample_levelset_pipeline,
+        pipelines.max_velocity_pipeline,
+        pipelines.residual_pipeline,
+    ]
+}
+
+/// Compiled pipelines of [`FluidPipelines`], looked up once per frame by the node.
+struct FluidComputePipelines<'a> {
+    initialize_velocity_pipeline: &'a ComputePipeline,
+    initialize_grid_center_pipeline: &'a ComputePipeline,
+    clear_grid_center_pipeline: &'a ComputePipeline,
+    update_grid_label_pipeline: &'a ComputePipeline,
+    update_face_fraction_pipeline: &'a ComputePipeline,
+    advection_pipeline: &'a ComputePipeline,
+    add_force_pipeline: &'a ComputePipeline,
+    vorticity_pipeline: &'a ComputePipeline,
+    vorticity_confinement_pipeline: &'a ComputePipeline,
+    viscosity_copy_pipeline: &'a ComputePipeline,
+    viscosity_explicit_pipeline: &'a ComputePipeline,
+    viscosity_jacobi_pipeline: &'a ComputePipeline,
+    viscosity_jacobi_reverse_pipeline: &'a ComputePipeline,
+    divergence_pipeline: &'a ComputePipeline,
+    jacobi_iteration_pipeline: &'a ComputePipeline,
+    jacobi_iteration_reverse_pipeline: &'a ComputePipeline,
+    red_black_sor_red_pipeline: &'a ComputePipeline,
+    red_black_sor_black_pipeline: &'a ComputePipeline,
+    multigrid_restrict_finest_pipeline: &'a ComputePipeline,
+    multigrid_restrict_coarse_pipeline: &'a ComputePipeline,
+    multigrid_prolongate_pipeline: &'a ComputePipeline,
+    multigrid_smooth_red_pipeline: &'a ComputePipeline,
+    multigrid_smooth_black_pipeline: &'a ComputePipeline,
+    pcg_initialize_pipeline: &'a ComputePipeline,
+    pcg_precondition_jacobi_pipeline: &'a ComputePipeline,
+    pcg_precondition_incomplete_poisson_upper_pipeline: &'a ComputePipeline,
+    pcg_precondition_incomplete_poisson_lower_pipeline: &'a ComputePipeline,
+    pcg_reduce_initial_pipeline: &'a ComputePipeline,
+    pcg_update_direction_pipeline: &'a ComputePipeline,
+    pcg_apply_pipeline: &'a ComputePipeline,
+    pcg_reduce_alpha_pipeline: &'a ComputePipeline,
+    pcg_update_pipeline: &'a ComputePipeline,
+    pcg_reduce_beta_pipeline: &'a ComputePipeline,
+    solve_velocity_pipeline: &'a ComputePipeline,
+    initialize_valid_faces_pipeline: &'a ComputePipeline,
+    extrapolate_velocity_pipeline: &'a ComputePipeline,
+    recompute_levelset_initialization_pipeline: &'a ComputePipeline,
+    recompute_levelset_iteration_pipeline: &'a ComputePipeline,
+    recompute_levelset_solve_pipeline: &'a ComputePipeline,
+    advect_levelset_pipeline: &'a ComputePipeline,
+    mac_cormack_velocity_forward_pipeline: &'a ComputePipeline,
+    mac_cormack_velocity_backward_pipeline: &'a ComputePipeline,
+    mac_cormack_velocity_correct_pipeline: &'a ComputePipeline,
+    mac_cormack_levelset_forward_pipeline: &'a ComputePipeline,
+    mac_cormack_levelset_backward_pipeline: &'a ComputePipeline,
+    mac_cormack_levelset_correct_pipeline: &'a ComputePipeline,
+    mac_cormack_levelset_copy_pipeline: &'a ComputePipeline,
+    resample_velocity_pipeline: &'a ComputePipeline,
+    resample_levelset_pipeline: &'a ComputePipeline,
+    max_velocity_pipeline: &'a ComputePipeline,
+    residual_pipeline: &'a ComputePipeline,
+}
+
+impl<'a> FluidComputePipelines<'a> {
+    /// Returns `None` unless every pipeline has been compiled.
+    fn new(pipeline_cache: &'a PipelineCache, pipelines: &FluidPipelines) -> Option<Self> {
+        let pipeline = |id| pipeline_cache.get_compute_pipeline(id);
+        Some(Self {
+            initialize_velocity_pipeline: pipeline(pipelines.initialize_velocity_pipeline)?,
+            initialize_grid_center_pipeline: pipeline(pipelines.initialize_grid_center_pipeline)?,
+            clear_grid_center_pipeline: pipeline(pipelines.clear_grid_center_pipeline)?,
+            update_grid_label_pipeline: pipeline(pipelines.update_grid_label_pipeline)?,
+            update_face_fraction_pipeline: pipeline(pipelines.update_face_fraction_pipeline)?,
+            advection_pipeline: pipeline(pipelines.advection_pipeline)?,
+            add_force_pipeline: pipeline(pipelines.add_force_pipeline)?,
+            vorticity_pipeline: pipeline(pipelines.vorticity_pipeline)?,
+            vorticity_confinement_pipeline: pipeline(pipelines.vorticity_confinement_pipeline)?,
+            viscosity_copy_pipeline: pipeline(pipelines.viscosity_copy_pipeline)?,
+            viscosity_explicit_pipeline: pipeline(pipelines.viscosity_explicit_pipeline)?,
+            viscosity_jacobi_pipeline: pipeline(pipelines.viscosity_jacobi_pipeline)?,
+            viscosity_jacobi_reverse_pipeline: pipeline(
+                pipelines.viscosity_jacobi_reverse_pipeline,
+            )?,
+            divergence_pipeline: pipeline(pipelines.divergence_pipeline)?,
+            jacobi_iteration_pipeline: pipeline(pipelines.jacobi_iteration_pipeline)?,
+            jacobi_iteration_reverse_pipeline: pipeline(
+                pipelines.jacobi_iteration_reverse_pipeline,
+            )?,
+            red_black_sor_red_pipeline: pipeline(pipelines.red_black_sor_red_pipeline)?,
+            red_black_sor_black_pipeline: pipeline(pipelines.red_black_sor_black_pipeline)?,
+            multigrid_restrict_finest_pipeline: pipeline(
+                pipelines.multigrid_restrict_finest_pipeline,
+            )?,
+            multigrid_restrict_coarse_pipeline: pipeline(
+                pipelines.multigrid_restrict_coarse_pipeline,
+            )?,
+            multigrid_prolongate_pipeline: pipeline(pipelines.multigrid_prolongate_pipeline)?,
+            multigrid_smooth_red_pipeline: pipeline(pipelines.multigrid_smooth_red_pipeline)?,
+            multigrid_smooth_black_pipeline: pipeline(pipelines.multigrid_smooth_black_pipeline)?,
+            pcg_initialize_pipeline: pipeline(pipelines.pcg_initialize_pipeline)?,
+            pcg_precondition_jacobi_pipeline: pipeline(pipelines.pcg_precondition_jacobi_pipeline)?,
+            pcg_precondition_incomplete_poisson_upper_pipeline: pipeline(
+                pipelines.pcg_precondition_incomplete_poisson_upper_pipeline,
+            )?,
+            pcg_precondition_incomplete_poisson_lower_pipeline: pipeline(
+                pipelines.pcg_precondition_incomplete_poisson_lower_pipeline,
+            )?,
+            pcg_reduce_initial_pipeline: pipeline(pipelines.pcg_reduce_initial_pipeline)?,
+            pcg_update_direction_pipeline: pipeline(pipelines.pcg_update_direction_pipeline)?,
+            pcg_apply_pipeline: pipeline(pipelines.pcg_apply_pipeline)?,
+            pcg_reduce_alpha_pipeline: pipeline(pipelines.pcg_reduce_alpha_pipeline)?,
+            pcg_update_pipeline: pipeline(pipelines.pcg_update_pipeline)?,
+            pcg_reduce_beta_pipeline: pipeline(pipelines.pcg_reduce_beta_pipeline)?,
+            solve_velocity_pipeline: pipeline(pipelines.solve_velocity_pipeline)?,
+            initialize_valid_faces_pipeline: pipeline(pipelines.initialize_valid_faces_pipeline)?,
+            extrapolate_velocity_pipeline: pipeline(pipelines.extrapolate_velocity_pipeline)?,
+            recompute_levelset_initialization_pipeline: pipeline(
+                pipelines.recompute_levelset_initialization_pipeline,
+            )?,
+            recompute_levelset_iteration_pipeline: pipeline(
+                pipelines.recompute_levelset_iteration_pipeline,
+            )?,
+            recompute_levelset_solve_pipeline: pipeline(
+                pipelines.recompute_levelset_solve_pipeline,
+            )?,
+            advect_levelset_pipeline: pipeline(pipelines.advect_levelset_pipeline)?,
+            mac_cormack_velocity_forward_pipeline: pipeline(
+                pipelines.mac_cormack_velocity_forward_pipeline,
+            )?,
+            mac_cormack_velocity_backward_pipeline: pipeline(
+                pipelines.mac_cormack_velocity_backward_pipeline,
+            )?,
+            mac_cormack_velocity_correct_pipeline: pipeline(
+                pipelines.mac_cormack_velocity_correct_pipeline,
+            )?,
+            mac_cormack_levelset_forward_pipeline: pipeline(
+                pipelines.mac_cormack_levelset_forward_pipeline,
+            )?,
+            mac_cormack_levelset_backward_pipeline: pipeline(
+                pipelines.mac_cormack_levelset_backward_pipeline,
+            )?,
+            mac_cormack_levelset_correct_pipeline: pipeline(
+                pipelines.mac_cormack_levelset_correct_pipeline,
+            )?,
+            mac_cormack_levelset_copy_pipeline: pipeline(
+                pipelines.mac_cormack_levelset_copy_pipeline,
+            )?,
+            resample_velocity_pipeline: pipeline(pipelines.resample_velocity_pipeline)?,
+            resample_levelset_pipeline: pipeline(pipelines.resample_levelset_pipeline)?,
+            max_velocity_pipeline: pipeline(pipelines.max_velocity_pipeline)?,
+            residual_pipeline: pipeline(pipelines.residual_pipeline)?,
+        })
+    }
+}
+
+/// Values of the reductions read back from the render world. Only the requested reductions are `Some`.
+/// * `max_velocity`: Maximum velocity in unit of [m/s].
+/// * `residual`: Maximum divergence of the velocity, not divided by `dx`, and the number of pressure iterations.
+pub(crate) struct FluidReadbackValues {
+    pub max_velocity: Option<f32>,
+    pub residual: Option<(f32, u32)>,
+}
+
+/// Values read back from the render world, with the main world entities.
+/// Shared between the main world and the render world, and drained on the main world
+/// to update [`FluidTimestep`](super::definition::FluidTimestep) and [`FluidPressureResidual`](super::definition::FluidPressureResidual).
+#[derive(Resource, Clone, Default)]
+pub(crate) struct FluidReadbackQueue(pub Arc<Mutex<Vec<(Entity, FluidReadbackValues)>>>);
+
+/// Maps the buffers copied by [`EulerFluidNode`] on this frame, and reads the ones mapped since the last frame.
+/// Mapping is not waited for, so the values arrive on the main world a few frames later than they are computed.
+pub(crate) fn readback_fluid(
+    query: Query<(&MainEntity, &FluidReadback)>,
+    render_device: Res<RenderDevice>,
+    queue: Res<FluidReadbackQueue>,
+) {
+    let mut values = Vec::new();
+    for (main_entity, readback) in &query {
+        match readback.state.load(Ordering::Acquire) {
+            READBACK_COPIED => {
+                readback.state.store(READBACK_MAPPING, Ordering::Release);
+                let state = readback.state.clone();
+                readback
+                    .staging_buffer
+                    .slice(..)
+                    .map_async(MapMode::Read, move |result| {
+                        let next = match result {
+                            Ok(()) => READBACK_MAPPED,
+                            Err(_) => READBACK_IDLE,
+                        };
+                        state.store(next, Ordering::Release);
+                    });
+            }
+            READBACK_MAPPED => {
+                let [max_velocity, residual, pcg_iterations] = {
+                    let view = readback.staging_buffer.slice(..).get_mapped_range();
+                    let value = |i: usize| {
+                        u32::from_le_bytes([
+                            view[4 * i],
+                            view[4 * i + 1],
+                            view[4 * i + 2],
+                            view[4 * i + 3],
+                        ])
+                    };
+                    [value(0), value(1), value(2)]
+                };
+                readback.staging_buffer.unmap();
+                readback.state.store(READBACK_IDLE, Ordering::Release);
+                let request = *readback.request.lock().unwrap();
+                values.push((
+                    main_entity.id(),
+                    FluidReadbackValues {
+                        max_velocity: request.max_velocity.then_some(f32::from_bits(max_velocity)),
+                        residual: request.residual.map(|iterations| {
+                            let iterations = if request.pcg_iterations {
+                                pcg_iterations
+                            } else {
+                                iterations
+                            };
+                            (f32::from_bits(residual), iterations)
+                        }),
+                    },
+                ));
+            }
+            _ => {}
+        }
+    }
+    render_device.poll(Maintain::Poll);
+
+    if !values.is_empty() {
+        queue.0.lock().unwrap().extend(values);
+    }
+}
+
+pub(crate) fn extract_reset_fluid(
+    mut commands: Commands,
+    mut events: Extract<EventReader<ResetFluid>>,
+    query: Extract<Query<&RenderEntity>>,
+) {
+    for event in events.read() {
+        let Ok(render_entity) = query.get(event.entity) else {
+            continue;
+        };
+        if let Some(mut entity_commands) = commands.get_entity(render_entity.id()) {
+            entity_commands.remove::<FluidSimulationState>();
+        }
+    }
+}
+
+/// Components of a simulation on the render world, extracted from the main world or prepared on the render world.
+type FluidRenderComponents = (
+    (
+        FluidSettings,
+        VelocityTextures,
+        PressureTextures,
+        DivergenceTextures,
+        LevelsetTextures,
+        JumpFloodingSeedsTextures,
+        LocalForces,
+        SimulationUniform,
+        DynamicUniformIndex<SimulationUniform>,
+        SimulationSteps,
+        PressureIterations,
+        ResolvedSolverSettings,
+    ),
+    (
+        FluidBindGroups,
+        FluidResampleBindGroups,
+        JumpFloodingUniformBindGroups,
+        JumpFloodingUniformBuffer,
+        FluidReadback,
+        MultigridBindGroups,
+        PcgBindGroups,
+        ExtrapolationBindGroups,
+        MacCormackBindGroups,
+        VorticityBindGroups,
+        ViscosityBindGroups,
+        FluidSimulationState,
+    ),
+);
+
+/// Tears down the simulation on the render world when [`FluidSettings`] has been removed from a main world entity which is still alive.
+/// Extraction never removes the components whose main world counterparts have been removed,
+/// so without this the node would keep dispatching on the textures returned to
+/// [`FluidTexturePool`](super::setup_components::FluidTexturePool) and keep them from being reused.
+pub(crate) fn extract_removed_fluid(
+    mut commands: Commands,
+    query: Query<(Entity, &MainEntity), With<FluidSettings>>,
+    main_query: Extract<Query<(), With<FluidSettings>>>,
+) {
+    for (entity, main_entity) in &query {
+        if !main_query.contains(main_entity.id()) {
+            commands.entity(entity).remove::<FluidRenderComponents>();
+        }
+    }
 }
 
+type FluidQueryData = (
+    Entity,
+    &'static FluidBindGroups,
+    &'static JumpFloodingUniformBindGroups,
+    &'static FluidSimulationState,
+    Option<&'static FluidResampleBindGroups>,
+    (&'static ResolvedSolverSettings, &'static SimulationUniform),
+    Option<&'static SimulationSteps>,
+    Option<&'static PressureIterations>,
+    Option<&'static FluidReadback>,
+    Option<&'static MultigridBindGroups>,
+    Option<&'static PcgBindGroups>,
+    Option<&'static ExtrapolationBindGroups>,
+    Option<&'static MacCormackBindGroups>,
+    Option<&'static VorticityBindGroups>,
+    Option<&'static ViscosityBindGroups>,
+);
+
 pub(crate) struct EulerFluidNode {
     state: State,
+    /// Render world entities whose initialization passes have been dispatched by `run` since the last `update`.
+    dispatched_initialization: Mutex<HashSet<Entity>>,
     // Query BindGroups components
     // Reference: bevy\crates\bevy_ui\src\render\render_pass.rs
-    query: QueryState<(
-        Entity,
-        &'static FluidSettings,
-        &'static FluidBindGroups,
-        &'static JumpFloodingUniformBindGroups,
-    )>,
+    query: QueryState<FluidQueryData>,
+    lifecycle_query: QueryState<
+        (
+            Entity,
+            &'static MainEntity,
+            Option<&'static FluidSimulationState>,
+        ),
+        With<FluidBindGroups>,
+    >,
 }
 
 impl EulerFluidNode {
     pub fn new(world: &mut World) -> Self {
         Self {
             state: State::Loading,
+            dispatched_initialization: Mutex::default(),
             query: world.query_filtered(),
+            lifecycle_query: world.query_filtered(),
         }
     }
 }
 
 impl render_graph::Node for EulerFluidNode {
     fn update(&mut self, world: &mut World) {
-        self.query.update_archetypes(world);
         let pipelines = world.resource::<FluidPipelines>();
         let pipeline_cache = world.resource::<PipelineCache>();
-        match self.state {
-            State::Loading => {
-                if let (
-                    CachedPipelineState::Ok(_initialize_velocity_pipeline),
-                    CachedPipelineState::Ok(_initialize_grid_center_pipeline),
-                ) = (
-                    pipeline_cache
-                        .get_compute_pipeline_state(pipelines.initialize_velocity_pipeline),
-                    pipeline_cache
-                        .get_compute_pipeline_state(pipelines.initialize_grid_center_pipeline),
-                ) {
-                    self.state = State::Init;
+        let ids = pipeline_ids(pipelines);
+        let errors = ids
+            .iter()
+            .filter_map(|id| match pipeline_cache.get_compute_pipeline_state(*id) {
+                // Shaders still being loaded are retried by the pipeline cache.
+                CachedPipelineState::Err(
+                    PipelineCacheError::ShaderNotLoaded(_)
+                    | PipelineCacheError::ShaderImportNotYetAvailable,
+                ) => None,
+                CachedPipelineState::Err(err) => {
+                    let label = pipeline_cache
+                        .get_compute_pipeline_descriptor(*id)
+                        .label
+                        .clone()
+                        .unwrap_or_default();
+                    Some(FluidError {
+                        entity: None,
+                        stage: FluidErrorStage::PipelineCompilation,
+                        message: format!("{label}: {err}"),
+                    })
                 }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let is_ready = ids.iter().all(|id| {
+            matches!(
+                pipeline_cache.get_compute_pipeline_state(*id),
+                CachedPipelineState::Ok(_)
+            )
+        });
+        if !errors.is_empty() {
+            if !matches!(self.state, State::Failed) {
+                let queue = world.resource::<FluidErrorQueue>();
+                queue.0.lock().unwrap().extend(errors);
+                self.state = State::Failed;
             }
-            State::Init => {
-                if let (
-                    CachedPipelineState::Ok(_update_grid_label_pipeline),
-                    CachedPipelineState::Ok(_advection_pipeline),
-                    CachedPipelineState::Ok(_add_force_pipeline),
-                    CachedPipelineState::Ok(_divergence_pipeline),
-                    CachedPipelineState::Ok(_jacobi_iteration_pipeline),
-                    CachedPipelineState::Ok(_jacobi_iteration_reverse_pipeline),
-                    CachedPipelineState::Ok(_solve_velocity_pipeline),
-                    CachedPipelineState::Ok(_recompute_levelset_initialization_pipeline),
-                    CachedPipelineState::Ok(_recompute_levelset_iteration_pipeline),
-                    CachedPipelineState::Ok(_recompute_levelset_solve_pipeline),
-                    CachedPipelineState::Ok(_advect_levelset_pipeline),
-                ) = (
-                    pipeline_cache.get_compute_pipeline_state(pipelines.update_grid_label_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(pipelines.advection_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(pipelines.add_force_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(pipelines.divergence_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(pipelines.jacobi_iteration_pipeline),
-                    pipeline_cache
-                        .get_compute_pipeline_state(pipelines.jacobi_iteration_reverse_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(pipelines.solve_velocity_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(
-                        pipelines.recompute_levelset_initialization_pipeline,
-                    ),
-                    pipeline_cache.get_compute_pipeline_state(
-                        pipelines.recompute_levelset_iteration_pipeline,
-                    ),
-                    pipeline_cache
-                        .get_compute_pipeline_state(pipelines.recompute_levelset_solve_pipeline),
-                    pipeline_cache.get_compute_pipeline_state(pipelines.advect_levelset_pipeline),
-                ) {
-                    self.state = State::Update;
+        } else if is_ready {
+            self.state = State::Ready;
+        } else {
+            // Pipelines are queued again when their shaders are reloaded.
+            self.state = State::Loading;
+        }
+
+        match self.state {
+            State::Loading | State::Failed => {}
+            State::Ready => {
+                // Entities whose initialization has been dispatched on the previous frame start running,
+                // and entities whose bind groups have just been prepared are queued for initialization.
+                // Entities skipped by `run`, e.g. while their bind groups are missing, stay initializing.
+                let dispatched =
+                    std::mem::take(&mut *self.dispatched_initialization.lock().unwrap());
+                let mut initialized = Vec::new();
+                let transitions = self
+                    .lifecycle_query
+                    .iter(world)
+                    .filter_map(|(entity, main_entity, state)| match state {
+                        None => Some((entity, FluidSimulationState::Initializing)),
+                        Some(FluidSimulationState::Initializing)
+                            if dispatched.contains(&entity) =>
+                        {
+                            initialized.push(main_entity.id());
+                            Some((entity, FluidSimulationState::Running))
+                        }
+                        Some(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                for (entity, state) in transitions {
+                    world.entity_mut(entity).insert(state);
+                }
+                if !initialized.is_empty() {
+                    let queue = world.resource::<FluidInitializedQueue>();
+                    queue.0.lock().unwrap().extend(initialized);
                 }
             }
-            State::Update => {}
         }
+        self.query.update_archetypes(world);
     }
     fn run<'w>(
         &self,
@@ -110,197 +570,626 @@ impl render_graph::Node for EulerFluidNode {
     ) -> Result<(), render_graph::NodeRunError> {
         let pipeline_cache = world.resource::<PipelineCache>();
         let pipelines = world.resource::<FluidPipelines>();
-        match self.state {
-            State::Loading => {}
-            State::Init => {
-                let initialize_velocity_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.initialize_velocity_pipeline)
-                    .unwrap();
-                let initialize_grid_center_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.initialize_grid_center_pipeline)
-                    .unwrap();
-                for (_entity, settings, bind_groups, _) in self.query.iter_manual(world) {
-                    let mut pass = render_context
-                        .command_encoder()
-                        .begin_compute_pass(&ComputePassDescriptor::default());
-                    let size = settings.size;
-
-                    pass.set_pipeline(&initialize_velocity_pipeline);
+        if !matches!(self.state, State::Ready) {
+            return Ok(());
+        }
+
+        // Every pipeline is compiled once the node is ready, as checked in `update`.
+        let Some(FluidComputePipelines {
+            initialize_velocity_pipeline,
+            initialize_grid_center_pipeline,
+            clear_grid_center_pipeline,
+            update_grid_label_pipeline,
+            update_face_fraction_pipeline,
+            advection_pipeline,
+            add_force_pipeline,
+            vorticity_pipeline,
+            vorticity_confinement_pipeline,
+            viscosity_copy_pipeline,
+            viscosity_explicit_pipeline,
+            viscosity_jacobi_pipeline,
+            viscosity_jacobi_reverse_pipeline,
+            divergence_pipeline,
+            jacobi_iteration_pipeline,
+            jacobi_iteration_reverse_pipeline,
+            red_black_sor_red_pipeline,
+            red_black_sor_black_pipeline,
+            multigrid_restrict_finest_pipeline,
+            multigrid_restrict_coarse_pipeline,
+            multigrid_prolongate_pipeline,
+            multigrid_smooth_red_pipeline,
+            multigrid_smooth_black_pipeline,
+            pcg_initialize_pipeline,
+            pcg_precondition_jacobi_pipeline,
+            pcg_precondition_incomplete_poisson_upper_pipeline,
+            pcg_precondition_incomplete_poisson_lower_pipeline,
+            pcg_reduce_initial_pipeline,
+            pcg_update_direction_pipeline,
+            pcg_apply_pipeline,
+            pcg_reduce_alpha_pipeline,
+            pcg_update_pipeline,
+            pcg_reduce_beta_pipeline,
+            solve_velocity_pipeline,
+            initialize_valid_faces_pipeline,
+            extrapolate_velocity_pipeline,
+            recompute_levelset_initialization_pipeline,
+            recompute_levelset_iteration_pipeline,
+            recompute_levelset_solve_pipeline,
+            advect_levelset_pipeline,
+            mac_cormack_velocity_forward_pipeline,
+            mac_cormack_velocity_backward_pipeline,
+            mac_cormack_velocity_correct_pipeline,
+            mac_cormack_levelset_forward_pipeline,
+            mac_cormack_levelset_backward_pipeline,
+            mac_cormack_levelset_correct_pipeline,
+            mac_cormack_levelset_copy_pipeline,
+            resample_velocity_pipeline,
+            resample_levelset_pipeline,
+            max_velocity_pipeline,
+            residual_pipeline,
+        }) = FluidComputePipelines::new(pipeline_cache, pipelines)
+        else {
+            return Ok(());
+        };
+        let pcg_pipelines = PcgPipelines {
+            initialize: pcg_initialize_pipeline,
+            precondition_jacobi: pcg_precondition_jacobi_pipeline,
+            precondition_incomplete_poisson_upper:
+                pcg_precondition_incomplete_poisson_upper_pipeline,
+            precondition_incomplete_poisson_lower:
+                pcg_precondition_incomplete_poisson_lower_pipeline,
+            reduce_initial: pcg_reduce_initial_pipeline,
+            update_direction: pcg_update_direction_pipeline,
+            apply: pcg_apply_pipeline,
+            reduce_alpha: pcg_reduce_alpha_pipeline,
+            update: pcg_update_pipeline,
+            reduce_beta: pcg_reduce_beta_pipeline,
+        };
+        let multigrid_pipelines = MultigridPipelines {
+            smooth_finest: [red_black_sor_red_pipeline, red_black_sor_black_pipeline],
+            smooth_coarse: [
+                multigrid_smooth_red_pipeline,
+                multigrid_smooth_black_pipeline,
+            ],
+            restrict_finest: multigrid_restrict_finest_pipeline,
+            restrict_coarse: multigrid_restrict_coarse_pipeline,
+            prolongate: multigrid_prolongate_pipeline,
+        };
+
+        let Some(bind_group_resources) = world.get_resource::<FluidBindGroupResources>() else {
+            return Ok(());
+        };
+        let config = world.resource::<FluidConfig>();
+        let mut dispatched_initialization = Vec::new();
+        for (
+            entity,
+            bind_groups,
+            jump_flooding_uniform_bind_groups,
+            state,
+            resample_bind_groups,
+            (solver_settings, uniform),
+            steps,
+            pressure_iterations,
+            readback,
+            multigrid,
+            pcg,
+            extrapolation,
+            mac_cormack,
+            vorticity,
+            viscosity,
+        ) in self.query.iter_manual(world)
+        {
+            let mut pass = render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor::default());
+            let size = bind_groups.size;
+            let solver_settings = &solver_settings.0;
+            // Workgroups for the passes on grid centers and on grid faces.
+            let workgroups = (
+                size.0.div_ceil(config.workgroup_size),
+                size.1.div_ceil(config.workgroup_size),
+            );
+            let face_workgroups = (size.0 + 1, size.1.div_ceil(config.face_workgroup_size));
+            let steps = steps.copied().unwrap_or_default();
+            let pressure_iterations = pressure_iterations
+                .map_or(solver_settings.pressure_solver.iterations(), |iterations| {
+                    iterations.0
+                });
+            // The maximum velocity is reduced only for adaptive timestep and the residual only periodically,
+            // and neither while paused nor until the previous values have been read back.
+            let residual = steps.reaches_interval(solver_settings.residual_interval);
+            let pcg = pcg
+                .filter(|_| matches!(solver_settings.pressure_solver, PressureSolver::Pcg { .. }));
+            let request = ReadbackRequest {
+                max_velocity: solver_settings.cfl.is_some(),
+                residual: residual.then_some(pressure_iterations),
+                pcg_iterations: residual && pcg.is_some(),
+            };
+            let readback = readback.filter(|readback| {
+                steps.steps > 0
+                    && (request.max_velocity || request.residual.is_some())
+                    && readback.state.load(Ordering::Acquire) == READBACK_IDLE
+                    && matches!(
+                        (state, resample_bind_groups),
+                        (FluidSimulationState::Running, None)
+                    )
+            });
+
+            match (state, resample_bind_groups) {
+                (FluidSimulationState::Initializing, _) => {
+                    dispatched_initialization.push(entity);
+                    pass.set_pipeline(initialize_velocity_pipeline);
                     pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
-                    pass.dispatch_workgroups(
-                        size.0 + 1,
-                        size.1 / WORKGROUP_SIZE / WORKGROUP_SIZE,
-                        1,
-                    );
+                    pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
 
-                    pass.set_pipeline(&initialize_grid_center_pipeline);
+                    pass.set_pipeline(initialize_grid_center_pipeline);
                     pass.set_bind_group(0, &bind_groups.levelset_bind_group, &[]);
                     pass.set_bind_group(
                         1,
                         &bind_groups.uniform_bind_group,
                         &[bind_groups.uniform_index],
                     );
-                    pass.dispatch_workgroups(size.0 / WORKGROUP_SIZE, size.1 / WORKGROUP_SIZE, 1);
-                }
-            }
-            State::Update => {
-                let update_grid_label_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.update_grid_label_pipeline)
-                    .unwrap();
-                let advection_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.advection_pipeline)
-                    .unwrap();
-                let add_force_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.add_force_pipeline)
-                    .unwrap();
-                let divergence_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.divergence_pipeline)
-                    .unwrap();
-                let jacobi_iteration_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.jacobi_iteration_pipeline)
-                    .unwrap();
-                let jacobi_iteration_reverse_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.jacobi_iteration_reverse_pipeline)
-                    .unwrap();
-                let solve_velocity_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.solve_velocity_pipeline)
-                    .unwrap();
-                let recompute_levelset_initialization_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.recompute_levelset_initialization_pipeline)
-                    .unwrap();
-                let recompute_levelset_itertation_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.recompute_levelset_iteration_pipeline)
-                    .unwrap();
-                let recompute_levelset_solve_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.recompute_levelset_solve_pipeline)
-                    .unwrap();
-                let advect_levelset_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipelines.advect_levelset_pipeline)
-                    .unwrap();
-
-                let bind_group_resources = world.resource::<FluidBindGroupResources>();
-                for (_entity, settings, bind_groups, jump_flooding_uniform_bind_groups) in
-                    self.query.iter_manual(world)
-                {
-                    let mut pass = render_context
-                        .command_encoder()
-                        .begin_compute_pass(&ComputePassDescriptor::default());
-                    let size = settings.size;
-
-                    pass.set_pipeline(&update_grid_label_pipeline);
-                    pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
-                    pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
-                    pass.set_bind_group(2, &bind_group_resources.obstacles_bind_group, &[]);
-                    pass.dispatch_workgroups(size.0 / WORKGROUP_SIZE, size.1 / WORKGROUP_SIZE, 1);
+                    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
 
-                    pass.set_pipeline(&advection_pipeline);
+                    pass.set_pipeline(clear_grid_center_pipeline);
+                    pass.set_bind_group(0, &bind_groups.pressure_bind_group, &[]);
+                    pass.set_bind_group(1, &bind_groups.divergence_bind_group, &[]);
+                    pass.set_bind_group(2, &bind_groups.jump_flooding_seeds_bind_group, &[]);
+                    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                }
+                (FluidSimulationState::Running, Some(resample_bind_groups)) => {
+                    // The textures have been reallocated. Carry the previous state over instead of stepping this frame.
+                    pass.set_pipeline(resample_velocity_pipeline);
                     pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
                     pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
-                    pass.set_bind_group(
-                        2,
-                        &bind_groups.uniform_bind_group,
-                        &[bind_groups.uniform_index],
-                    );
+                    pass.set_bind_group(2, &resample_bind_groups.velocity_bind_group, &[]);
+                    pass.set_bind_group(3, &resample_bind_groups.levelset_bind_group, &[]);
                     pass.dispatch_workgroups(
-                        size.0 + 1,
-                        size.1 / WORKGROUP_SIZE / WORKGROUP_SIZE,
+                        (size.0 + 1).div_ceil(config.workgroup_size),
+                        (size.1 + 1).div_ceil(config.workgroup_size),
                         1,
                     );
 
-                    pass.set_pipeline(&add_force_pipeline);
-                    pass.set_bind_group(
-                        1,
-                        &bind_groups.uniform_bind_group,
-                        &[bind_groups.uniform_index],
-                    );
-                    pass.set_bind_group(2, &bind_groups.local_forces_bind_group, &[]);
-                    pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
-                    pass.dispatch_workgroups(
-                        size.0 + 1,
-                        size.1 / WORKGROUP_SIZE / WORKGROUP_SIZE,
-                        1,
-                    );
+                    pass.set_pipeline(resample_levelset_pipeline);
+                    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
 
-                    pass.set_pipeline(&divergence_pipeline);
+                    // Textures taken from the pool may hold values of a previous simulation.
+                    pass.set_pipeline(clear_grid_center_pipeline);
+                    pass.set_bind_group(0, &bind_groups.pressure_bind_group, &[]);
                     pass.set_bind_group(1, &bind_groups.divergence_bind_group, &[]);
-                    pass.set_bind_group(2, &bind_groups.levelset_bind_group, &[]);
-                    pass.dispatch_workgroups(size.0 / WORKGROUP_SIZE, size.1 / WORKGROUP_SIZE, 1);
+                    pass.set_bind_group(2, &bind_groups.jump_flooding_seeds_bind_group, &[]);
+                    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                }
+                (FluidSimulationState::Running, None) => {
+                    // The whole step is dispatched for each substep of the steps the fixed timestep has ticked.
+                    // Paused simulations take no step.
+                    for substep in 0..steps.total_substeps() {
+                        pass.set_pipeline(update_grid_label_pipeline);
+                        pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
+                        pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
+                        pass.set_bind_group(2, &bind_group_resources.obstacles_bind_group, &[]);
+                        pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
 
-                    pass.set_bind_group(
-                        0,
-                        &bind_groups.uniform_bind_group,
-                        &[bind_groups.uniform_index],
-                    );
-                    pass.set_bind_group(1, &bind_groups.pressure_bind_group, &[]);
-                    pass.set_bind_group(2, &bind_groups.divergence_bind_group, &[]);
-                    pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
-                    for _ in 0..5 {
-                        pass.set_pipeline(&jacobi_iteration_pipeline);
-                        pass.dispatch_workgroups(
-                            size.0 / WORKGROUP_SIZE,
-                            size.1 / WORKGROUP_SIZE,
-                            1,
+                        pass.set_pipeline(update_face_fraction_pipeline);
+                        pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+
+                        pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
+                        pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
+                        pass.set_bind_group(
+                            2,
+                            &bind_groups.uniform_bind_group,
+                            &[bind_groups.uniform_index],
                         );
-                        pass.set_pipeline(&jacobi_iteration_reverse_pipeline);
-                        pass.dispatch_workgroups(
-                            size.0 / WORKGROUP_SIZE,
-                            size.1 / WORKGROUP_SIZE,
+                        if let Some(mac_cormack) = mac_cormack {
+                            pass.set_bind_group(3, &mac_cormack.bind_group, &[]);
+                            for pipeline in [
+                                mac_cormack_velocity_forward_pipeline,
+                                mac_cormack_velocity_backward_pipeline,
+                                mac_cormack_velocity_correct_pipeline,
+                            ] {
+                                pass.set_pipeline(pipeline);
+                                pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+                            }
+                        } else {
+                            pass.set_pipeline(advection_pipeline);
+                            pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+                        }
+
+                        if let Some(vorticity) = vorticity {
+                            pass.set_bind_group(3, &vorticity.bind_group, &[]);
+                            pass.set_pipeline(vorticity_pipeline);
+                            pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+
+                            pass.set_pipeline(vorticity_confinement_pipeline);
+                            pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+                        }
+
+                        pass.set_pipeline(add_force_pipeline);
+                        pass.set_bind_group(
                             1,
+                            &bind_groups.uniform_bind_group,
+                            &[bind_groups.uniform_index],
                         );
-                    }
+                        pass.set_bind_group(2, &bind_groups.local_forces_bind_group, &[]);
+                        pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
+                        pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
 
-                    pass.set_pipeline(&solve_velocity_pipeline);
-                    pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
-                    pass.set_bind_group(
-                        1,
-                        &bind_groups.uniform_bind_group,
-                        &[bind_groups.uniform_index],
-                    );
-                    pass.set_bind_group(2, &bind_groups.pressure_bind_group, &[]);
-                    pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
-                    pass.dispatch_workgroups(
-                        size.0 + 1,
-                        size.1 / WORKGROUP_SIZE / WORKGROUP_SIZE,
-                        1,
-                    );
+                        if let Some(viscosity) = viscosity {
+                            pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
+                            pass.set_bind_group(
+                                2,
+                                &bind_groups.uniform_bind_group,
+                                &[bind_groups.uniform_index],
+                            );
+                            pass.set_bind_group(3, &viscosity.bind_group, &[]);
+                            pass.set_pipeline(viscosity_copy_pipeline);
+                            pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+                            let alpha = uniform.viscosity * uniform.dt / (uniform.dx * uniform.dx);
+                            match solver_settings.viscosity_solver.stable_for(alpha) {
+                                ViscositySolver::Explicit => {
+                                    pass.set_pipeline(viscosity_explicit_pipeline);
+                                    pass.dispatch_workgroups(
+                                        face_workgroups.0,
+                                        face_workgroups.1,
+                                        1,
+                                    );
+                                }
+                                ViscositySolver::Implicit { iterations } => {
+                                    for _ in 0..iterations {
+                                        pass.set_pipeline(viscosity_jacobi_pipeline);
+                                        pass.dispatch_workgroups(
+                                            face_workgroups.0,
+                                            face_workgroups.1,
+                                            1,
+                                        );
+                                        pass.set_pipeline(viscosity_jacobi_reverse_pipeline);
+                                        pass.dispatch_workgroups(
+                                            face_workgroups.0,
+                                            face_workgroups.1,
+                                            1,
+                                        );
+                                    }
+                                }
+                            }
+                        }
 
-                    // recompute levelset
-                    pass.set_pipeline(&recompute_levelset_initialization_pipeline);
-                    pass.set_bind_group(0, &bind_groups.levelset_bind_group, &[]);
-                    pass.set_bind_group(1, &bind_groups.jump_flooding_seeds_bind_group, &[]);
-                    pass.dispatch_workgroups(size.0 / WORKGROUP_SIZE, size.1 / WORKGROUP_SIZE, 1);
-
-                    pass.set_pipeline(&recompute_levelset_itertation_pipeline);
-                    pass.set_bind_group(0, &bind_groups.jump_flooding_seeds_bind_group, &[]);
-                    for bind_group in
-                        &jump_flooding_uniform_bind_groups.jump_flooding_step_bind_groups
-                    {
-                        pass.set_bind_group(1, bind_group, &[]);
-                        pass.dispatch_workgroups(
-                            size.0 / WORKGROUP_SIZE,
-                            size.1 / WORKGROUP_SIZE,
+                        pass.set_pipeline(divergence_pipeline);
+                        pass.set_bind_group(1, &bind_groups.divergence_bind_group, &[]);
+                        pass.set_bind_group(2, &bind_groups.levelset_bind_group, &[]);
+                        pass.set_bind_group(3, &bind_group_resources.obstacles_bind_group, &[]);
+                        pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+
+                        pass.set_bind_group(
+                            0,
+                            &bind_groups.uniform_bind_group,
+                            &[bind_groups.uniform_index],
+                        );
+                        pass.set_bind_group(1, &bind_groups.pressure_bind_group, &[]);
+                        pass.set_bind_group(2, &bind_groups.divergence_bind_group, &[]);
+                        pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
+                        match (solver_settings.pressure_solver, multigrid, pcg) {
+                            (PressureSolver::Multigrid { smoothing, .. }, Some(multigrid), _) => {
+                                for _ in 0..pressure_iterations {
+                                    dispatch_v_cycle(
+                                        &mut pass,
+                                        &multigrid_pipelines,
+                                        bind_groups,
+                                        multigrid,
+                                        smoothing,
+                                        config.workgroup_size,
+                                    );
+                                }
+                            }
+                            (PressureSolver::Pcg { preconditioner, .. }, _, Some(pcg)) => {
+                                dispatch_pcg(
+                                    &mut pass,
+                                    &pcg_pipelines,
+                                    pcg,
+                                    preconditioner,
+                                    pressure_iterations,
+                                    workgroups,
+                                );
+                            }
+                            // Multigrid levels and PCG buffers are created a frame later than the solver is switched,
+                            // so Gauss–Seidel is used in the meantime.
+                            (solver, _, _) => {
+                                let (first_pipeline, second_pipeline) = match solver {
+                                    PressureSolver::Jacobi { .. } => (
+                                        jacobi_iteration_pipeline,
+                                        jacobi_iteration_reverse_pipeline,
+                                    ),
+                                    PressureSolver::RedBlackSor { .. }
+                                    | PressureSolver::Multigrid { .. }
+                                    | PressureSolver::Pcg { .. } => {
+                                        (red_black_sor_red_pipeline, red_black_sor_black_pipeline)
+                                    }
+                                };
+                                for _ in 0..pressure_iterations {
+                                    pass.set_pipeline(first_pipeline);
+                                    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                                    pass.set_pipeline(second_pipeline);
+                                    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                                }
+                            }
+                        }
+
+                        pass.set_pipeline(solve_velocity_pipeline);
+                        pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
+                        pass.set_bind_group(
                             1,
+                            &bind_groups.uniform_bind_group,
+                            &[bind_groups.uniform_index],
+                        );
+                        pass.set_bind_group(2, &bind_groups.pressure_bind_group, &[]);
+                        pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
+                        pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+
+                        if let Some(extrapolation) = extrapolation {
+                            pass.set_pipeline(initialize_valid_faces_pipeline);
+                            pass.set_bind_group(1, &extrapolation.bind_groups[0], &[]);
+                            pass.set_bind_group(2, &bind_groups.levelset_bind_group, &[]);
+                            pass.set_bind_group(
+                                3,
+                                &bind_groups.uniform_bind_group,
+                                &[bind_groups.uniform_index],
+                            );
+                            pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+
+                            pass.set_pipeline(extrapolate_velocity_pipeline);
+                            for layer in 0..solver_settings.extrapolation_layers {
+                                let bind_group = &extrapolation.bind_groups[layer as usize % 2];
+                                pass.set_bind_group(1, bind_group, &[]);
+                                pass.dispatch_workgroups(face_workgroups.0, face_workgroups.1, 1);
+                            }
+                        }
+
+                        // recompute levelset
+                        if steps.is_at_interval(
+                            substep,
+                            solver_settings.levelset_reinitialization_interval,
+                        ) {
+                            pass.set_pipeline(recompute_levelset_initialization_pipeline);
+                            pass.set_bind_group(0, &bind_groups.levelset_bind_group, &[]);
+                            pass.set_bind_group(
+                                1,
+                                &bind_groups.jump_flooding_seeds_bind_group,
+                                &[],
+                            );
+                            pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+
+                            pass.set_pipeline(recompute_levelset_iteration_pipeline);
+                            pass.set_bind_group(
+                                0,
+                                &bind_groups.jump_flooding_seeds_bind_group,
+                                &[],
+                            );
+                            for bind_group in
+                                &jump_flooding_uniform_bind_groups.jump_flooding_step_bind_groups
+                            {
+                                pass.set_bind_group(1, bind_group, &[]);
+                                pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                            }
+
+                            pass.set_pipeline(recompute_levelset_solve_pipeline);
+                            pass.set_bind_group(0, &bind_groups.levelset_bind_group, &[]);
+                            pass.set_bind_group(
+                                1,
+                                &bind_groups.jump_flooding_seeds_bind_group,
+                                &[],
+                            );
+                            pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                        }
+
+                        pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
+                        pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
+                        pass.set_bind_group(
+                            2,
+                            &bind_groups.uniform_bind_group,
+                            &[bind_groups.uniform_index],
                         );
+                        if let Some(mac_cormack) = mac_cormack {
+                            pass.set_bind_group(3, &mac_cormack.bind_group, &[]);
+                            for pipeline in [
+                                mac_cormack_levelset_forward_pipeline,
+                                mac_cormack_levelset_backward_pipeline,
+                                mac_cormack_levelset_correct_pipeline,
+                                mac_cormack_levelset_copy_pipeline,
+                            ] {
+                                pass.set_pipeline(pipeline);
+                                pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                            }
+                        } else {
+                            pass.set_pipeline(advect_levelset_pipeline);
+                            pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                        }
                     }
 
-                    pass.set_pipeline(&recompute_levelset_solve_pipeline);
-                    pass.set_bind_group(0, &bind_groups.levelset_bind_group, &[]);
-                    pass.set_bind_group(1, &bind_groups.jump_flooding_seeds_bind_group, &[]);
-                    pass.dispatch_workgroups(size.0 / WORKGROUP_SIZE, size.1 / WORKGROUP_SIZE, 1);
+                    if let Some(readback) = readback {
+                        if request.max_velocity {
+                            pass.set_pipeline(max_velocity_pipeline);
+                            pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
+                            pass.set_bind_group(1, &readback.bind_group, &[]);
+                            pass.dispatch_workgroups(
+                                (size.0 + 1).div_ceil(config.workgroup_size),
+                                (size.1 + 1).div_ceil(config.workgroup_size),
+                                1,
+                            );
+                        }
+                        if request.residual.is_some() {
+                            pass.set_pipeline(residual_pipeline);
+                            pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
+                            pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
+                            pass.set_bind_group(2, &readback.bind_group, &[]);
+                            pass.set_bind_group(3, &bind_group_resources.obstacles_bind_group, &[]);
+                            pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                        }
+                    }
+                }
+            }
+            drop(pass);
 
-                    pass.set_pipeline(&advect_levelset_pipeline);
-                    pass.set_bind_group(0, &bind_groups.velocity_bind_group, &[]);
-                    pass.set_bind_group(1, &bind_groups.levelset_bind_group, &[]);
-                    pass.set_bind_group(
-                        2,
-                        &bind_groups.uniform_bind_group,
-                        &[bind_groups.uniform_index],
+            if let Some(readback) = readback {
+                let encoder = render_context.command_encoder();
+                if let (true, Some(pcg)) = (request.pcg_iterations, pcg) {
+                    encoder.copy_buffer_to_buffer(
+                        &pcg.scalars,
+                        PCG_ITERATIONS_OFFSET,
+                        &readback.buffer,
+                        2 * size_of::<u32>() as u64,
+                        size_of::<u32>() as u64,
                     );
-                    pass.dispatch_workgroups(size.0 / WORKGROUP_SIZE, size.1 / WORKGROUP_SIZE, 1);
                 }
+                encoder.copy_buffer_to_buffer(
+                    &readback.buffer,
+                    0,
+                    &readback.staging_buffer,
+                    0,
+                    readback.buffer.size(),
+                );
+                encoder.clear_buffer(&readback.buffer, 0, None);
+                *readback.request.lock().unwrap() = request;
+                readback.state.store(READBACK_COPIED, Ordering::Release);
             }
         }
 
+        self.dispatched_initialization
+            .lock()
+            .unwrap()
+            .extend(dispatched_initialization);
         Ok(())
     }
 }
+
+/// Pipelines of a V-cycle of [`PressureSolver::Multigrid`].
+struct MultigridPipelines<'a> {
+    smooth_finest: [&'a ComputePipeline; 2],
+    smooth_coarse: [&'a ComputePipeline; 2],
+    restrict_finest: &'a ComputePipeline,
+    restrict_coarse: &'a ComputePipeline,
+    prolongate: &'a ComputePipeline,
+}
+
+/// Dispatches a V-cycle over the levels of `multigrid`, updating the pressure in place on `p1`.
+/// The uniform bind group is expected to be set to the group 0, which all the pipelines share.
+fn dispatch_v_cycle(
+    pass: &mut ComputePass,
+    pipelines: &MultigridPipelines,
+    bind_groups: &FluidBindGroups,
+    multigrid: &MultigridBindGroups,
+    smoothing: u32,
+    workgroup_size: u32,
+) {
+    let workgroups = |level: usize| {
+        let size = multigrid.sizes[level];
+        (
+            size.0.div_ceil(workgroup_size),
+            size.1.div_ceil(workgroup_size),
+        )
+    };
+    // The finest level is smoothed with the bind groups of the red-black SOR.
+    let smooth = |pass: &mut ComputePass, level: usize, sweeps: u32| {
+        let smooth_pipelines = if level == 0 {
+            pass.set_bind_group(1, &bind_groups.pressure_bind_group, &[]);
+            pass.set_bind_group(2, &bind_groups.divergence_bind_group, &[]);
+            pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
+            pipelines.smooth_finest
+        } else {
+            pass.set_bind_group(1, &multigrid.bind_groups[level], &[]);
+            pipelines.smooth_coarse
+        };
+        let workgroups = workgroups(level);
+        for _ in 0..sweeps {
+            for pipeline in smooth_pipelines {
+                pass.set_pipeline(pipeline);
+                pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+            }
+        }
+    };
+
+    let coarsest = multigrid.sizes.len() - 1;
+    for level in 0..coarsest {
+        smooth(pass, level, smoothing);
+        let restrict_pipeline = if level == 0 {
+            pipelines.restrict_finest
+        } else {
+            pipelines.restrict_coarse
+        };
+        pass.set_pipeline(restrict_pipeline);
+        pass.set_bind_group(1, &multigrid.bind_groups[level], &[]);
+        pass.set_bind_group(2, &multigrid.bind_groups[level + 1], &[]);
+        if level == 0 {
+            pass.set_bind_group(3, &bind_groups.levelset_bind_group, &[]);
+        }
+        let workgroups = workgroups(level + 1);
+        pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+    }
+
+    smooth(pass, coarsest, MULTIGRID_COARSEST_SMOOTHING);
+
+    for level in (0..coarsest).rev() {
+        pass.set_pipeline(pipelines.prolongate);
+        pass.set_bind_group(1, &multigrid.bind_groups[level], &[]);
+        pass.set_bind_group(2, &multigrid.bind_groups[level + 1], &[]);
+        let workgroups = workgroups(level);
+        pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+        smooth(pass, level, smoothing);
+    }
+}
+
+/// Pipelines of [`PressureSolver::Pcg`], named after the entry points of pcg.wgsl.
+struct PcgPipelines<'a> {
+    initialize: &'a ComputePipeline,
+    precondition_jacobi: &'a ComputePipeline,
+    precondition_incomplete_poisson_upper: &'a ComputePipeline,
+    precondition_incomplete_poisson_lower: &'a ComputePipeline,
+    reduce_initial: &'a ComputePipeline,
+    update_direction: &'a ComputePipeline,
+    apply: &'a ComputePipeline,
+    reduce_alpha: &'a ComputePipeline,
+    update: &'a ComputePipeline,
+    reduce_beta: &'a ComputePipeline,
+}
+
+/// Dispatches `iterations` of preconditioned conjugate gradient, updating the pressure in place on `p1`.
+/// The iterations after convergence are dispatched but leave the pressure untouched.
+/// The uniform bind group is expected to be set to the group 0.
+fn dispatch_pcg(
+    pass: &mut ComputePass,
+    pipelines: &PcgPipelines,
+    pcg: &PcgBindGroups,
+    preconditioner: PcgPreconditioner,
+    iterations: u32,
+    workgroups: (u32, u32),
+) {
+    pass.set_bind_group(1, &pcg.bind_group, &[]);
+    let mut dispatch = |pipeline: &ComputePipeline, workgroups: (u32, u32)| {
+        pass.set_pipeline(pipeline);
+        pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+    };
+    // Reductions over the partial sums run on a single workgroup.
+    let single = (1, 1);
+    let precondition_pipelines = match preconditioner {
+        PcgPreconditioner::Jacobi => vec![pipelines.precondition_jacobi],
+        PcgPreconditioner::IncompletePoisson => vec![
+            pipelines.precondition_incomplete_poisson_upper,
+            pipelines.precondition_incomplete_poisson_lower,
+        ],
+    };
+
+    dispatch(pipelines.initialize, workgroups);
+    for &pipeline in &precondition_pipelines {
+        dispatch(pipeline, workgroups);
+    }
+    dispatch(pipelines.reduce_initial, single);
+    dispatch(pipelines.update_direction, workgroups);
+    for _ in 0..iterations {
+        dispatch(pipelines.apply, workgroups);
+        dispatch(pipelines.reduce_alpha, single);
+        dispatch(pipelines.update, workgroups);
+        for &pipeline in &precondition_pipelines {
+            dispatch(pipeline, workgroups);
+        }
+        dispatch(pipelines.reduce_beta, single);
+        dispatch(pipelines.update_direction, workgroups);
+    }
+}
@@ -1,86 +1,232 @@
+use std::sync::Arc;
+
 use bevy::{
     prelude::*,
-    render::{render_resource::TextureFormat, storage::ShaderStorageBuffer},
+    render::{
+        extract_component::ExtractComponent, render_resource::TextureFormat,
+        storage::ShaderStorageBuffer,
+    },
+    utils::{HashMap, HashSet},
 };
 
 use crate::{
     euler_fluid::definition::{
-        FluidSimulationBundle, LocalForces, PressureTextures, SimulationUniform, VelocityTextures,
+        FluidError, FluidInitialized, FluidSimulationBundle, FluidTextures, LocalForces,
+        PressureTextures, SimulationUniform, VelocityTextures,
     },
     texture::NewTexture,
 };
 
+use super::render_node::{FluidErrorQueue, FluidInitializedQueue, FluidReadbackQueue};
+
 use super::definition::{
-    DivergenceTextures, FluidSettings, JumpFloodingSeedsTextures, LevelsetTextures,
+    DivergenceTextures, FluidConfig, FluidPaused, FluidPressureResidual, FluidSettings,
+    FluidSolverSettings, FluidTimestep, JumpFloodingSeedsTextures, LevelsetTextures,
+    PressureSolver, SimulationControl,
 };
 
-pub(crate) fn watch_fluid_component(
-    mut commands: Commands,
-    query: Query<(Entity, &FluidSettings), Added<FluidSettings>>,
-    mut images: ResMut<Assets<Image>>,
-    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
-) {
-    for (entity, settings) in &query {
-        let size = settings.size;
+/// Size of the textures currently allocated for a simulation.
+/// Used to detect size changes on [`FluidSettings`].
+#[derive(Component)]
+pub(crate) struct AllocatedSize(pub (u32, u32));
 
-        if size.0 != size.1 {
-            warn!("the size is recommended to be same between size.0 and size.1. {size:?}");
-        }
-        if size.0 % 64 != 0 || size.1 % 64 != 0 {
-            warn!("the size is recommended to be multiple of 64. {size:?}");
-        }
-        let size_u = (size.0 + 1, size.1);
-        let size_v = (size.0, size.1 + 1);
+/// Number of steps to advance the simulation on this frame, the number of substeps each step is split into,
+/// and the number of steps taken before this frame.
+/// The intervals of [`FluidSolverSettings`] are counted in the steps, so that they depend neither on the frame rate nor on the substeps.
+#[derive(Component, ExtractComponent, Clone, Copy, Default)]
+pub(crate) struct SimulationSteps {
+    pub steps: u32,
+    pub substeps: u32,
+    pub elapsed: u64,
+}
 
-        let u0 = images.new_texture_storage(size_u, TextureFormat::R32Float);
-        let u1 = images.new_texture_storage(size_u, TextureFormat::R32Float);
+impl SimulationSteps {
+    /// Number of substeps to dispatch on this frame.
+    pub fn total_substeps(&self) -> u32 {
+        self.steps * self.substeps
+    }
 
-        let v0 = images.new_texture_storage(size_v, TextureFormat::R32Float);
-        let v1 = images.new_texture_storage(size_v, TextureFormat::R32Float);
+    /// Whether the `substep`-th substep on this frame ends a step at a multiple of `interval` since the simulation started.
+    pub fn is_at_interval(&self, substep: u32, interval: u32) -> bool {
+        let substeps = self.substeps.max(1);
+        interval != 0
+            && (substep + 1) % substeps == 0
+            && (self.elapsed + (substep / substeps) as u64 + 1) % interval as u64 == 0
+    }
 
-        let div = images.new_texture_storage(size, TextureFormat::R32Float);
+    /// Whether any step on this frame is at a multiple of `interval` since the simulation started.
+    pub fn reaches_interval(&self, interval: u32) -> bool {
+        interval != 0
+            && (self.elapsed + self.steps as u64) / interval as u64 > self.elapsed / interval as u64
+    }
+}
 
-        let p0 = images.new_texture_storage(size, TextureFormat::R32Float);
-        let p1 = images.new_texture_storage(size, TextureFormat::R32Float);
+/// Number of pressure iterations on this frame.
+/// Equal to the iterations of [`FluidSolverSettings::pressure_solver`] unless adapted to [`FluidSolverSettings::pressure_tolerance`].
+#[derive(Component, ExtractComponent, Clone, Copy)]
+pub(crate) struct PressureIterations(pub u32);
 
-        let grid_label = images.new_texture_storage(size, TextureFormat::R32Uint);
+/// Solver settings in effect on a simulation: [`FluidSolverSettings`] of the entity if present, [`FluidConfig::solver`] otherwise.
+/// Extracted to the render world instead of the optional [`FluidSolverSettings`],
+/// so that removing the override falls back to the defaults on the render world as well.
+#[derive(Component, ExtractComponent, Clone, Copy)]
+pub(crate) struct ResolvedSolverSettings(pub FluidSolverSettings);
 
-        let levelset = images.new_texture_storage(size, TextureFormat::R32Float);
-        let jump_flooding_seeds_x = images.new_texture_storage(size, TextureFormat::R32Float);
-        let jump_flooding_seeds_y = images.new_texture_storage(size, TextureFormat::R32Float);
+/// Ticks of [`Time<Fixed>`] not yet consumed as simulation steps.
+#[derive(Resource, Default)]
+pub(crate) struct PendingFixedSteps(pub u32);
 
-        let force = buffers.add(ShaderStorageBuffer::from(vec![Vec2::ZERO; 0]));
-        let position = buffers.add(ShaderStorageBuffer::from(vec![Vec2::ZERO; 0]));
+/// Pool of simulation textures released by torn down fluids.
+/// Textures are reused for the fluids spawned later with the same size instead of allocating new [`Image`]s.
+///
+/// Textures are returned to the pool when [`FluidSettings`] is removed from an entity or the entity is despawned,
+/// and when `size` of [`FluidSettings`] is changed.
+/// A set of textures is reused only after every other handle to them has been dropped,
+/// so materials created from the handles keep showing the fluid they were created for.
+///
+/// # Examples
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_eulerian_fluid::setup_components::FluidTexturePool;
+///
+/// let mut images = Assets::<Image>::default();
+/// let mut pool = FluidTexturePool::default();
+///
+/// let textures = pool.acquire((64, 64), &mut images);
+/// let u0 = textures.velocity_textures.u0.id();
+/// pool.release((64, 64), textures);
+/// assert_eq!(pool.len(), 1);
+///
+/// // Released textures are reused for the same size.
+/// let textures = pool.acquire((64, 64), &mut images);
+/// assert_eq!(textures.velocity_textures.u0.id(), u0);
+/// assert!(pool.is_empty());
+///
+/// // Textures still referenced elsewhere, e.g. by a material, are not reused.
+/// let material_texture = textures.velocity_textures.u0.clone();
+/// pool.release((64, 64), textures);
+/// let textures = pool.acquire((64, 64), &mut images);
+/// assert_ne!(textures.velocity_textures.u0.id(), material_texture.id());
+/// ```
+#[derive(Resource)]
+pub struct FluidTexturePool {
+    /// Maximum number of texture sets kept for each size. Texture sets released beyond this are dropped.
+    pub capacity_per_size: usize,
+    textures: HashMap<(u32, u32), Vec<FluidTextures>>,
+}
+
+impl Default for FluidTexturePool {
+    fn default() -> Self {
+        Self {
+            capacity_per_size: 8,
+            textures: HashMap::default(),
+        }
+    }
+}
 
-        let velocity_textures = VelocityTextures { u0, v0, u1, v1 };
+impl FluidTexturePool {
+    /// Takes a set of textures with the given size from the pool, or allocates new one if none is available.
+    pub fn acquire(&mut self, size: (u32, u32), images: &mut Assets<Image>) -> FluidTextures {
+        if let Some(textures) = self.textures.get_mut(&size) {
+            if let Some(index) = textures.iter().position(is_unique) {
+                return textures.swap_remove(index);
+            }
+        }
 
-        let pressure_textures = PressureTextures { p0, p1 };
+        allocate_textures(size, images)
+    }
 
-        let divergence_textures = DivergenceTextures { div };
+    /// Returns a set of textures to the pool.
+    /// When the pool is full for the size, the sets still referenced elsewhere are dropped first to make room,
+    /// since they cannot be handed out and may stay referenced for as long as the materials using them live.
+    pub fn release(&mut self, size: (u32, u32), textures: FluidTextures) {
+        let pooled = self.textures.entry(size).or_default();
+        if pooled.len() >= self.capacity_per_size {
+            pooled.retain(is_unique);
+        }
+        if pooled.len() < self.capacity_per_size {
+            pooled.push(textures);
+        }
+    }
 
-        let levelset_textures = LevelsetTextures {
-            levelset,
-            grid_label,
-        };
+    /// Number of texture sets kept in the pool.
+    pub fn len(&self) -> usize {
+        self.textures.values().map(Vec::len).sum()
+    }
 
-        let uniform = SimulationUniform {
-            dx: settings.dx,
-            dt: settings.dt,
-            rho: settings.rho,
-            gravity: settings.gravity,
-            initial_fluid_level: settings.initial_fluid_level,
-        };
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all the texture sets kept in the pool.
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+}
+
+type FluidTexturesQueryData<'a> = (
+    &'a VelocityTextures,
+    &'a PressureTextures,
+    &'a DivergenceTextures,
+    &'a LevelsetTextures,
+    &'a JumpFloodingSeedsTextures,
+);
+
+/// Clones the handles of the textures of a simulation to return them to [`FluidTexturePool`].
+fn cloned_textures(
+    (
+        velocity_textures,
+        pressure_textures,
+        divergence_textures,
+        levelset_textures,
+        jump_flooding_seeds_textures,
+    ): FluidTexturesQueryData,
+) -> FluidTextures {
+    FluidTextures {
+        velocity_textures: velocity_textures.clone(),
+        pressure_textures: pressure_textures.clone(),
+        divergence_textures: divergence_textures.clone(),
+        levelset_textures: levelset_textures.clone(),
+        jump_flooding_seeds_textures: jump_flooding_seeds_textures.clone(),
+    }
+}
+
+fn is_unique(textures: &FluidTextures) -> bool {
+    textures
+        .handles()
+        .into_iter()
+        .all(|handle| matches!(handle, Handle::Strong(handle) if Arc::strong_count(handle) == 1))
+}
+
+pub(crate) fn watch_fluid_component(
+    mut commands: Commands,
+    query: Query<(Entity, &FluidSettings, Option<&FluidSolverSettings>), Added<FluidSettings>>,
+    mut images: ResMut<Assets<Image>>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    mut pool: ResMut<FluidTexturePool>,
+    config: Res<FluidConfig>,
+) {
+    for (entity, settings, solver_settings) in &query {
+        if let Err(err) = settings.validate() {
+            warn!("invalid fluid settings on {entity}: {err}");
+        }
+        let solver_settings = *solver_settings.unwrap_or(&config.solver);
+        let FluidTextures {
+            velocity_textures,
+            pressure_textures,
+            divergence_textures,
+            levelset_textures,
+            jump_flooding_seeds_textures,
+        } = pool.acquire(settings.size, &mut images);
+
+        let force = buffers.add(ShaderStorageBuffer::from(vec![Vec2::ZERO; 0]));
+        let position = buffers.add(ShaderStorageBuffer::from(vec![Vec2::ZERO; 0]));
 
         let local_forces = LocalForces {
             forces: force,
             positions: position,
         };
 
-        let jump_flooding_seeds_textures = JumpFloodingSeedsTextures {
-            jump_flooding_seeds_x,
-            jump_flooding_seeds_y,
-        };
-
         commands
             .entity(entity)
             .insert(FluidSimulationBundle {
@@ -91,6 +237,669 @@ pub(crate) fn watch_fluid_component(
                 levelset_textures,
                 jump_flooding_seeds_textures,
             })
-            .insert(uniform);
+            .insert(simulation_uniform(settings, &solver_settings))
+            .insert(ResolvedSolverSettings(solver_settings))
+            .insert(AllocatedSize(settings.size))
+            .insert(SimulationSteps::default())
+            .insert(PressureIterations(
+                solver_settings.pressure_solver.iterations(),
+            ))
+            .insert(FluidTimestep {
+                dt: settings.dt,
+                ..default()
+            });
+    }
+}
+
+type FluidSettingsQueryData<'a> = (
+    Entity,
+    Ref<'a, FluidSettings>,
+    Option<Ref<'a, FluidSolverSettings>>,
+    &'a mut ResolvedSolverSettings,
+    &'a mut SimulationUniform,
+    &'a mut AllocatedSize,
+);
+
+/// Reflects changes on [`FluidSettings`] and [`FluidSolverSettings`] to the running simulation.
+/// Scalar parameters are copied to [`SimulationUniform`], and the solver settings to [`ResolvedSolverSettings`].
+/// When `size` is changed, all the textures are reallocated and the current velocity and levelset are resampled into the new grid on the render world.
+pub(crate) fn watch_fluid_settings_change(
+    mut commands: Commands,
+    mut query: Query<FluidSettingsQueryData>,
+    mut removed_solver_settings: RemovedComponents<FluidSolverSettings>,
+    config: Res<FluidConfig>,
+    textures: Query<FluidTexturesQueryData>,
+    mut images: ResMut<Assets<Image>>,
+    mut pool: ResMut<FluidTexturePool>,
+) {
+    let removed_solver_settings = removed_solver_settings.read().collect::<HashSet<_>>();
+    for (
+        entity,
+        settings,
+        solver_settings,
+        mut resolved_solver_settings,
+        mut uniform,
+        mut allocated_size,
+    ) in &mut query
+    {
+        let solver_changed = match &solver_settings {
+            Some(solver_settings) => solver_settings.is_changed(),
+            None => config.is_changed() || removed_solver_settings.contains(&entity),
+        };
+        if !settings.is_changed() && !solver_changed {
+            continue;
+        }
+        if let Err(err) = settings.validate() {
+            warn!("invalid fluid settings on {entity}: {err}");
+        }
+        if solver_changed {
+            resolved_solver_settings.0 = *solver_settings.as_deref().unwrap_or(&config.solver);
+        }
+        *uniform = simulation_uniform(&settings, &resolved_solver_settings.0);
+
+        if allocated_size.0 != settings.size {
+            info!(
+                "reallocate fluid textures: {:?} -> {:?}",
+                allocated_size.0, settings.size
+            );
+            // The previous textures are returned to the pool, and reused once the render world has dropped them after resampling.
+            if let Ok(previous) = textures.get(entity) {
+                pool.release(allocated_size.0, cloned_textures(previous));
+            }
+            commands
+                .entity(entity)
+                .insert(pool.acquire(settings.size, &mut images));
+            allocated_size.0 = settings.size;
+        }
+    }
+}
+
+pub(crate) fn count_fixed_steps(mut pending: ResMut<PendingFixedSteps>) {
+    pending.0 += 1;
+}
+
+type SimulationStepsQueryData<'a> = (
+    Entity,
+    Ref<'a, FluidSettings>,
+    Ref<'a, ResolvedSolverSettings>,
+    &'a mut SimulationSteps,
+    &'a mut SimulationUniform,
+    &'a mut FluidTimestep,
+    Option<&'a mut FluidPaused>,
+);
+
+/// Decides the number of steps of each simulation on this frame.
+/// Running simulations take a step per tick of [`Time<Fixed>`] since the last frame, up to [`FluidConfig::max_substeps`].
+/// Paused simulations by [`SimulationControl`] or [`FluidPaused`] take only the steps requested on them, one per frame.
+/// With adaptive timestep, each step is split into substeps and `dt` of [`SimulationUniform`] is overwritten by the one of a substep.
+/// The substeps are limited so that the steps times the substeps stay within [`FluidConfig::max_substeps`].
+///
+/// Ticks beyond [`FluidConfig::max_substeps`] are dropped, and a warning is logged when frames start dropping them.
+/// A warning is also logged when [`FluidSettings::dt`] differs from the timestep of [`Time<Fixed>`],
+/// since the simulation then runs faster or slower than real time,
+/// and when [`ViscositySolver::Explicit`](super::definition::ViscositySolver::Explicit) falls back to the implicit solver on the substep.
+pub(crate) fn update_simulation_steps(
+    mut query: Query<SimulationStepsQueryData>,
+    mut control: ResMut<SimulationControl>,
+    mut pending: ResMut<PendingFixedSteps>,
+    config: Res<FluidConfig>,
+    time: Option<Res<Time<Fixed>>>,
+    mut dropping: Local<bool>,
+) {
+    let fixed_steps = pending.0.min(config.max_substeps);
+    let dropped = pending.0 - fixed_steps;
+    pending.0 = 0;
+    if dropped > 0 && !*dropping && !query.is_empty() {
+        warn!(
+            "dropped {dropped} fluid steps exceeding FluidConfig::max_substeps ({}) on a frame, the simulation runs slower than real time",
+            config.max_substeps
+        );
+    }
+    *dropping = dropped > 0;
+
+    let control_step = control.paused && control.steps > 0;
+    for (entity, settings, solver_settings, mut steps, mut uniform, mut timestep, paused) in
+        &mut query
+    {
+        if let Some(time) = &time {
+            let fixed_dt = time.timestep().as_secs_f32();
+            if settings.is_changed() && (settings.dt - fixed_dt).abs() > 1e-4 * fixed_dt {
+                warn!(
+                    "dt of fluid settings on {entity} ({}) differs from the timestep of Time<Fixed> ({fixed_dt}), the simulation runs at {}x real time",
+                    settings.dt,
+                    settings.dt / fixed_dt
+                );
+            }
+        }
+        let full_steps = match paused {
+            Some(mut paused) if paused.steps > 0 => {
+                paused.steps -= 1;
+                1
+            }
+            Some(_) => control_step as u32,
+            None if control.paused => control_step as u32,
+            None => fixed_steps,
+        };
+
+        // Substeps are limited so that all the steps on this frame fit in `max_substeps`.
+        // Each step still advances by the whole `dt`, since the substeps split it instead of being dropped.
+        let max_substeps = config.max_substeps / full_steps.max(1);
+        let cfl = solver_settings.0.cfl;
+        let required_substeps =
+            cfl.map_or(1, |cfl| cfl_substeps(&settings, cfl, timestep.max_velocity));
+        let substeps = required_substeps.min(max_substeps.max(1));
+        let dt = settings.dt / substeps as f32;
+        steps.elapsed += steps.steps as u64;
+        steps.steps = full_steps;
+        steps.substeps = substeps;
+        let viscosity_solver = solver_settings.0.viscosity_solver;
+        let alpha = uniform.viscosity * dt / (uniform.dx * uniform.dx);
+        if (uniform.dt != dt || settings.is_changed() || solver_settings.is_changed())
+            && viscosity_solver.stable_for(alpha) != viscosity_solver
+        {
+            warn!(
+                "explicit viscosity on {entity} diverges with viscosity * dt / dx^2 = {alpha}, solving it implicitly instead"
+            );
+        }
+        if uniform.dt != dt {
+            uniform.dt = dt;
+        }
+        timestep.dt = dt;
+        timestep.substeps = substeps;
+        timestep.clamped = substeps < required_substeps;
+    }
+    if control_step {
+        control.steps -= 1;
+    }
+}
+
+/// Number of substeps to split a step into so that the fluid travels at most `cfl` cells per substep.
+fn cfl_substeps(settings: &FluidSettings, cfl: f32, max_velocity: f32) -> u32 {
+    if cfl <= 0.0 || !max_velocity.is_finite() {
+        return 1;
+    }
+    let substeps = (settings.dt * max_velocity / (cfl * settings.dx)).ceil() as u32;
+    substeps.max(1)
+}
+
+/// Reflects the values read back from the render world to [`FluidTimestep`] and [`FluidPressureResidual`].
+pub(crate) fn receive_fluid_readback(
+    mut commands: Commands,
+    queue: Res<FluidReadbackQueue>,
+    mut query: Query<(&FluidSettings, &mut FluidTimestep)>,
+) {
+    let values = std::mem::take(&mut *queue.0.lock().unwrap());
+    for (entity, values) in values {
+        let Ok((settings, mut timestep)) = query.get_mut(entity) else {
+            continue;
+        };
+        if let Some(max_velocity) = values.max_velocity {
+            timestep.max_velocity = max_velocity;
+        }
+        if let Some((residual, iterations)) = values.residual {
+            commands.entity(entity).insert(FluidPressureResidual {
+                residual: residual / settings.dx,
+                iterations,
+            });
+        }
+    }
+}
+
+type PressureIterationsQueryData<'a> = (
+    &'a ResolvedSolverSettings,
+    Option<Ref<'a, FluidPressureResidual>>,
+    &'a mut PressureIterations,
+);
+
+/// Adapts the number of pressure iterations to [`FluidSolverSettings::pressure_tolerance`] on each reported residual.
+/// The iterations are doubled while the residual exceeds the tolerance,
+/// and reduced by a quarter while it is below half of the tolerance, within the iterations of [`FluidSolverSettings::pressure_solver`].
+pub(crate) fn update_pressure_iterations(mut query: Query<PressureIterationsQueryData>) {
+    for (solver_settings, residual, mut iterations) in &mut query {
+        let solver_settings = &solver_settings.0;
+        let max_iterations = solver_settings.pressure_solver.iterations();
+        let next = match (solver_settings.pressure_tolerance, residual) {
+            (Some(tolerance), Some(residual)) if residual.is_changed() => {
+                if residual.residual > tolerance {
+                    (residual.iterations * 2).max(1)
+                } else if residual.residual < 0.5 * tolerance {
+                    residual
+                        .iterations
+                        .saturating_sub((residual.iterations / 4).max(1))
+                } else {
+                    residual.iterations
+                }
+            }
+            (Some(_), _) => iterations.0,
+            (None, _) => max_iterations,
+        }
+        .clamp(max_iterations.min(1), max_iterations);
+        if iterations.0 != next {
+            iterations.0 = next;
+        }
+    }
+}
+
+/// Tears down the simulation when [`FluidSettings`] is removed, including when the entity is despawned.
+/// Components inserted by the plugin are removed and the textures are returned to [`FluidTexturePool`].
+/// The render world counterparts, such as bind groups and jump flooding uniform buffers,
+/// are dropped on the next extraction by [`extract_removed_fluid`](super::render_node::extract_removed_fluid),
+/// or together with the render world entity if the entity is despawned.
+pub(crate) fn teardown_fluid(
+    trigger: Trigger<OnRemove, FluidSettings>,
+    mut commands: Commands,
+    query: Query<(FluidTexturesQueryData, &AllocatedSize)>,
+    mut pool: ResMut<FluidTexturePool>,
+) {
+    let entity = trigger.entity();
+    let Ok((textures, allocated_size)) = query.get(entity) else {
+        return;
+    };
+    pool.release(allocated_size.0, cloned_textures(textures));
+
+    // Nothing to do if the entity is being despawned.
+    commands.entity(entity).remove::<(
+        FluidSimulationBundle,
+        SimulationUniform,
+        ResolvedSolverSettings,
+        AllocatedSize,
+        SimulationSteps,
+        PressureIterations,
+        FluidTimestep,
+        FluidPressureResidual,
+    )>();
+}
+
+/// Sends [`FluidInitialized`] for the entities initialized on the render world.
+pub(crate) fn send_fluid_initialized(
+    queue: Res<FluidInitializedQueue>,
+    query: Query<(), With<FluidSettings>>,
+    mut events: EventWriter<FluidInitialized>,
+) {
+    let initialized = std::mem::take(&mut *queue.0.lock().unwrap());
+    // The entity might have been despawned while the render world was working on it.
+    events.send_batch(
+        initialized
+            .into_iter()
+            .filter(|entity| query.contains(*entity))
+            .map(|entity| FluidInitialized { entity }),
+    );
+}
+
+/// Sends [`FluidError`] for the failures occurred on the render world.
+pub(crate) fn send_fluid_errors(queue: Res<FluidErrorQueue>, mut events: EventWriter<FluidError>) {
+    let errors = std::mem::take(&mut *queue.0.lock().unwrap());
+    events.send_batch(errors);
+}
+
+fn simulation_uniform(
+    settings: &FluidSettings,
+    solver_settings: &FluidSolverSettings,
+) -> SimulationUniform {
+    SimulationUniform {
+        dx: settings.dx,
+        dt: settings.dt,
+        rho: settings.rho,
+        gravity: settings.gravity,
+        initial_fluid_level: settings.initial_fluid_level,
+        force_kernel_radius: solver_settings.force_kernel_radius,
+        sor_omega: match solver_settings.pressure_solver {
+            PressureSolver::RedBlackSor { omega, .. } => omega,
+            _ => 1.0,
+        },
+        pcg_tolerance: match solver_settings.pressure_solver {
+            PressureSolver::Pcg { tolerance, .. } => tolerance,
+            _ => 0.0,
+        },
+        interpolation: solver_settings.interpolation as u32,
+        vorticity_confinement: solver_settings.vorticity_confinement,
+        viscosity: settings.viscosity,
+        air_rho: settings.air_rho.unwrap_or(0.0),
+        surface_tension: settings.surface_tension,
+    }
+}
+
+fn allocate_textures(size: (u32, u32), images: &mut Assets<Image>) -> FluidTextures {
+    let size_u = (size.0 + 1, size.1);
+    let size_v = (size.0, size.1 + 1);
+
+    let u0 = images.new_texture_storage(size_u, TextureFormat::R32Float);
+    let u1 = images.new_texture_storage(size_u, TextureFormat::R32Float);
+
+    let v0 = images.new_texture_storage(size_v, TextureFormat::R32Float);
+    let v1 = images.new_texture_storage(size_v, TextureFormat::R32Float);
+
+    let div = images.new_texture_storage(size, TextureFormat::R32Float);
+
+    let p0 = images.new_texture_storage(size, TextureFormat::R32Float);
+    let p1 = images.new_texture_storage(size, TextureFormat::R32Float);
+
+    let grid_label = images.new_texture_storage(size, TextureFormat::R32Uint);
+    let u_fraction = images.new_texture_storage(size_u, TextureFormat::R32Float);
+    let v_fraction = images.new_texture_storage(size_v, TextureFormat::R32Float);
+
+    let levelset = images.new_texture_storage(size, TextureFormat::R32Float);
+    let jump_flooding_seeds_x = images.new_texture_storage(size, TextureFormat::R32Float);
+    let jump_flooding_seeds_y = images.new_texture_storage(size, TextureFormat::R32Float);
+
+    let velocity_textures = VelocityTextures { u0, v0, u1, v1 };
+
+    let pressure_textures = PressureTextures { p0, p1 };
+
+    let divergence_textures = DivergenceTextures { div };
+
+    let levelset_textures = LevelsetTextures {
+        levelset,
+        grid_label,
+        u_fraction,
+        v_fraction,
+    };
+
+    let jump_flooding_seeds_textures = JumpFloodingSeedsTextures {
+        jump_flooding_seeds_x,
+        jump_flooding_seeds_y,
+    };
+
+    FluidTextures {
+        velocity_textures,
+        pressure_textures,
+        divergence_textures,
+        levelset_textures,
+        jump_flooding_seeds_textures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::system::RunSystemOnce,
+        render::{sync_world::MainEntity, MainWorld},
+    };
+
+    use super::*;
+    use crate::euler_fluid::render_node::extract_removed_fluid;
+
+    fn fluid_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>()
+            .init_resource::<Assets<ShaderStorageBuffer>>()
+            .init_resource::<FluidTexturePool>()
+            .init_resource::<FluidConfig>()
+            .add_systems(
+                Update,
+                (watch_fluid_component, watch_fluid_settings_change).chain(),
+            )
+            .add_observer(teardown_fluid);
+        app
+    }
+
+    fn settings() -> FluidSettings {
+        FluidSettings::builder().size((64, 64)).build().unwrap()
+    }
+
+    #[test]
+    fn cfl_substeps_keep_cfl() {
+        // dt = 0.5 and dx = 1.0, so 10 m/s travels 5 cells per step.
+        let settings = settings();
+        assert_eq!(cfl_substeps(&settings, 1.0, 10.0), 5);
+        assert_eq!(cfl_substeps(&settings, 2.0, 10.0), 3);
+        assert_eq!(cfl_substeps(&settings, 1.0, 0.0), 1);
+        assert_eq!(cfl_substeps(&settings, 1.0, f32::INFINITY), 1);
+        assert_eq!(cfl_substeps(&settings, 0.0, 10.0), 1);
+    }
+
+    /// Runs [`update_pressure_iterations`] on a fluid solving pressure with up to 20 iterations.
+    fn adapted_iterations(
+        pressure_tolerance: Option<f32>,
+        iterations: u32,
+        residual: Option<FluidPressureResidual>,
+    ) -> u32 {
+        let mut world = World::new();
+        let solver_settings = FluidSolverSettings {
+            pressure_solver: PressureSolver::Jacobi { iterations: 20 },
+            pressure_tolerance,
+            ..default()
+        };
+        let mut entity = world.spawn((
+            ResolvedSolverSettings(solver_settings),
+            PressureIterations(iterations),
+        ));
+        if let Some(residual) = residual {
+            entity.insert(residual);
+        }
+        let entity = entity.id();
+        world.run_system_once(update_pressure_iterations).unwrap();
+        world.get::<PressureIterations>(entity).unwrap().0
+    }
+
+    #[test]
+    fn pressure_iterations_adapt_to_residual() {
+        let residual = |residual, iterations| {
+            Some(FluidPressureResidual {
+                residual,
+                iterations,
+            })
+        };
+        // Doubled above the tolerance, up to the iterations of the solver.
+        assert_eq!(adapted_iterations(Some(1.0), 5, residual(2.0, 5)), 10);
+        assert_eq!(adapted_iterations(Some(1.0), 15, residual(2.0, 15)), 20);
+        // Reduced by a quarter below half of the tolerance, down to 1.
+        assert_eq!(adapted_iterations(Some(1.0), 8, residual(0.1, 8)), 6);
+        assert_eq!(adapted_iterations(Some(1.0), 1, residual(0.1, 1)), 1);
+        // Kept in between.
+        assert_eq!(adapted_iterations(Some(1.0), 8, residual(0.7, 8)), 8);
+        // Kept until a residual is reported.
+        assert_eq!(adapted_iterations(Some(1.0), 8, None), 8);
+        // The iterations of the solver without tolerance.
+        assert_eq!(adapted_iterations(None, 8, residual(2.0, 8)), 20);
+    }
+
+    #[test]
+    fn intervals_are_counted_in_steps() {
+        // Steps 5 and 6 on this frame, split into 3 substeps each.
+        let steps = SimulationSteps {
+            steps: 2,
+            substeps: 3,
+            elapsed: 4,
+        };
+        assert_eq!(steps.total_substeps(), 6);
+        assert!(!steps.is_at_interval(2, 2));
+        assert!(!steps.is_at_interval(3, 2));
+        assert!(steps.is_at_interval(5, 2));
+        assert!(steps.is_at_interval(2, 5));
+        assert!(!steps.is_at_interval(0, 5));
+        assert!(!steps.is_at_interval(2, 0));
+        assert!(steps.reaches_interval(3));
+        assert!(!steps.reaches_interval(4));
+        assert!(!steps.reaches_interval(0));
+
+        let paused = SimulationSteps {
+            steps: 0,
+            substeps: 1,
+            elapsed: 4,
+        };
+        assert!(!paused.reaches_interval(2));
+    }
+
+    #[test]
+    fn substeps_clamped_by_max_substeps_are_reported() {
+        let mut app = fluid_app();
+        app.insert_resource(FluidConfig {
+            max_substeps: 4,
+            ..default()
+        })
+        .init_resource::<SimulationControl>()
+        .init_resource::<PendingFixedSteps>()
+        .add_systems(
+            Update,
+            update_simulation_steps.after(watch_fluid_settings_change),
+        );
+        let solver_settings = FluidSolverSettings {
+            cfl: Some(1.0),
+            ..default()
+        };
+        let entity = app.world_mut().spawn((settings(), solver_settings)).id();
+        app.update();
+
+        // 10 m/s travels 5 cells per step, which needs 5 substeps on each of the 2 steps.
+        app.world_mut().resource_mut::<PendingFixedSteps>().0 = 2;
+        app.world_mut()
+            .get_mut::<FluidTimestep>(entity)
+            .unwrap()
+            .max_velocity = 10.0;
+        app.update();
+
+        let steps = *app.world().get::<SimulationSteps>(entity).unwrap();
+        assert_eq!((steps.steps, steps.substeps), (2, 2));
+        let timestep = app.world().get::<FluidTimestep>(entity).unwrap();
+        assert_eq!(timestep.substeps, 2);
+        assert!(timestep.clamped);
+    }
+
+    #[test]
+    fn teardown_returns_textures_to_pool() {
+        let mut app = fluid_app();
+        let entity = app.world_mut().spawn(settings()).id();
+        app.update();
+        let u0 = app.world().get::<VelocityTextures>(entity).unwrap().u0.id();
+
+        app.world_mut().entity_mut(entity).remove::<FluidSettings>();
+        app.update();
+
+        let entity_ref = app.world().entity(entity);
+        assert!(!entity_ref.contains::<VelocityTextures>());
+        assert!(!entity_ref.contains::<SimulationUniform>());
+        assert!(!entity_ref.contains::<SimulationSteps>());
+        assert!(!entity_ref.contains::<PressureIterations>());
+        assert!(!entity_ref.contains::<FluidTimestep>());
+
+        let world = app.world_mut();
+        world.resource_scope(|world, mut pool: Mut<FluidTexturePool>| {
+            assert_eq!(pool.len(), 1);
+            let mut images = world.resource_mut::<Assets<Image>>();
+            let textures = pool.acquire((64, 64), &mut images);
+            assert_eq!(textures.velocity_textures.u0.id(), u0);
+        });
+    }
+
+    #[test]
+    fn removed_solver_settings_fall_back_to_config() {
+        let mut app = fluid_app();
+        let solver_settings = FluidSolverSettings {
+            pressure_solver: PressureSolver::RedBlackSor {
+                omega: 1.7,
+                iterations: 20,
+            },
+            ..default()
+        };
+        let entity = app.world_mut().spawn((settings(), solver_settings)).id();
+        app.update();
+        let resolved = app.world().get::<ResolvedSolverSettings>(entity).unwrap();
+        assert_eq!(resolved.0.pressure_solver, solver_settings.pressure_solver);
+
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<FluidSolverSettings>();
+        app.update();
+        let resolved = app.world().get::<ResolvedSolverSettings>(entity).unwrap();
+        let config = app.world().resource::<FluidConfig>();
+        assert_eq!(resolved.0.pressure_solver, config.solver.pressure_solver);
+    }
+
+    #[test]
+    fn resize_returns_previous_textures_to_pool() {
+        let mut app = fluid_app();
+        let entity = app.world_mut().spawn(settings()).id();
+        app.update();
+        let u0 = app.world().get::<VelocityTextures>(entity).unwrap().u0.id();
+
+        app.world_mut()
+            .get_mut::<FluidSettings>(entity)
+            .unwrap()
+            .size = (128, 128);
+        app.update();
+
+        let world = app.world_mut();
+        world.resource_scope(|world, mut pool: Mut<FluidTexturePool>| {
+            assert_eq!(pool.len(), 1);
+            let mut images = world.resource_mut::<Assets<Image>>();
+            let textures = pool.acquire((64, 64), &mut images);
+            assert_eq!(textures.velocity_textures.u0.id(), u0);
+        });
+    }
+
+    #[test]
+    fn release_evicts_shared_textures_when_full() {
+        let mut images = Assets::<Image>::default();
+        let mut pool = FluidTexturePool {
+            capacity_per_size: 2,
+            ..default()
+        };
+        // Sets kept alive by materials cannot be reused.
+        let shared = [
+            pool.acquire((64, 64), &mut images),
+            pool.acquire((64, 64), &mut images),
+        ];
+        for textures in &shared {
+            pool.release((64, 64), textures.clone());
+        }
+        assert_eq!(pool.len(), 2);
+
+        let textures = pool.acquire((64, 64), &mut images);
+        let u0 = textures.velocity_textures.u0.id();
+        pool.release((64, 64), textures);
+        assert_eq!(pool.len(), 1);
+        let textures = pool.acquire((64, 64), &mut images);
+        assert_eq!(textures.velocity_textures.u0.id(), u0);
+    }
+
+    #[test]
+    fn teardown_removes_render_world_components() {
+        let mut app = fluid_app();
+        let kept = app.world_mut().spawn(settings()).id();
+        let removed = app.world_mut().spawn(settings()).id();
+        app.update();
+        app.world_mut()
+            .entity_mut(removed)
+            .remove::<FluidSettings>();
+        app.update();
+
+        let mut render_world = World::new();
+        let render_kept = render_world
+            .spawn((
+                MainEntity::from(kept),
+                settings(),
+                SimulationSteps {
+                    steps: 1,
+                    substeps: 1,
+                    elapsed: 0,
+                },
+                PressureIterations(1),
+            ))
+            .id();
+        let render_removed = render_world
+            .spawn((
+                MainEntity::from(removed),
+                settings(),
+                SimulationSteps {
+                    steps: 1,
+                    substeps: 1,
+                    elapsed: 0,
+                },
+                PressureIterations(1),
+            ))
+            .id();
+        let mut main_world = MainWorld::default();
+        std::mem::swap(&mut *main_world, app.world_mut());
+        render_world.insert_resource(main_world);
+        render_world.run_system_once(extract_removed_fluid).unwrap();
+
+        let render_kept = render_world.entity(render_kept);
+        assert!(render_kept.contains::<FluidSettings>());
+        assert!(render_kept.contains::<SimulationSteps>());
+        let render_removed = render_world.entity(render_removed);
+        assert!(!render_removed.contains::<FluidSettings>());
+        assert!(!render_removed.contains::<SimulationSteps>());
+        assert!(!render_removed.contains::<PressureIterations>());
     }
 }
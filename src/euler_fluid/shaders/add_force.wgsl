@@ -1,4 +1,5 @@
 #import bevy_fluid::fluid_uniform::SimulationUniform;
+#import bevy_fluid::ghost_fluid::face_density;
 
 @group(0) @binding(2) var u1: texture_storage_2d<r32float, read_write>;
 @group(0) @binding(3) var v1: texture_storage_2d<r32float, read_write>;
@@ -11,7 +12,7 @@
 @group(3) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
 
 @compute
-@workgroup_size(1, 64, 1)
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
 fn add_force(
     @builtin(global_invocation_id) invocation_id: vec3<u32>,
 ) {
@@ -21,10 +22,12 @@ fn add_force(
     var net_force = vec2<f32>(0.0, 0.0);
     let levelset_u = textureLoad(levelset, x_u).r;
     let levelset_v = textureLoad(levelset, x_v).r;
-    if (levelset_u < 0.0) {
+    // The air has weight as well in two-phase flow.
+    let two_phase = constants.air_rho > 0.0;
+    if (levelset_u < 0.0 || two_phase) {
         net_force.x = constants.gravity.x;
     }
-    if (levelset_v < 0.0) {
+    if (levelset_v < 0.0 || two_phase) {
         net_force.y = constants.gravity.y;
     }
 
@@ -35,17 +38,83 @@ fn add_force(
         n = n - 1u;
         let f = force[n];
         let p = position[n];
-        let force_u = f.x * gaussian_2d(vec2<f32>(x_u), p, 10.0);
-        let force_v = f.y * gaussian_2d(vec2<f32>(x_v), p, 10.0);
+        let force_u = f.x * gaussian_2d(vec2<f32>(x_u), p, constants.force_kernel_radius);
+        let force_v = f.y * gaussian_2d(vec2<f32>(x_v), p, constants.force_kernel_radius);
         net_force = net_force + vec2<f32>(force_u, force_v);
     }
 
+    if (constants.surface_tension > 0.0) {
+        net_force += vec2<f32>(
+            surface_tension(x_u - vec2<i32>(1, 0), x_u),
+            surface_tension(x_v - vec2<i32>(0, 1), x_v),
+        );
+    }
+
     let u_val = textureLoad(u1, x_u).r;
     let v_val = textureLoad(v1, x_v).r;
     textureStore(u1, x_u, vec4<f32>(u_val + net_force.x * constants.dt, 0.0, 0.0, 0.0));
     textureStore(v1, x_v, vec4<f32>(v_val + net_force.y * constants.dt, 0.0, 0.0, 0.0));
 }
 
+// Half width in cells of the band around the interface where the surface tension is spread.
+const SURFACE_TENSION_BAND: f32 = 1.5;
+
+// Acceleration by the surface tension on the face between the cells x0 and x1 with the continuum surface force,
+// surface_tension * curvature * grad(H) / rho, where H is the smoothed indicator of the fluid.
+fn surface_tension(x0: vec2<i32>, x1: vec2<i32>) -> f32 {
+    let levelset0 = levelset_at(x0);
+    let levelset1 = levelset_at(x1);
+    let gradient = (fluid_indicator(levelset1) - fluid_indicator(levelset0)) / constants.dx;
+    if (gradient == 0.0) {
+        return 0.0;
+    }
+    let kappa = 0.5 * (curvature(x0) + curvature(x1));
+    let rho = constants.rho * face_density(levelset0, levelset1, constants.air_rho / constants.rho);
+    return constants.surface_tension * kappa * gradient / rho;
+}
+
+// Smoothed Heaviside function which is 1 in the fluid and 0 in the air.
+fn fluid_indicator(levelset_value: f32) -> f32 {
+    let phi = levelset_value / SURFACE_TENSION_BAND;
+    if (phi <= -1.0) {
+        return 1.0;
+    }
+    if (phi >= 1.0) {
+        return 0.0;
+    }
+    let pi = 3.14159265;
+    return 0.5 - 0.5 * phi - sin(pi * phi) / (2.0 * pi);
+}
+
+// Curvature of the levelset contour through the cell x in unit of [1/m], positive where the fluid is convex.
+// Limited to the curvature of a circle of one cell radius.
+fn curvature(x: vec2<i32>) -> f32 {
+    let center = levelset_at(x);
+    let left = levelset_at(x - vec2<i32>(1, 0));
+    let right = levelset_at(x + vec2<i32>(1, 0));
+    let bottom = levelset_at(x - vec2<i32>(0, 1));
+    let top = levelset_at(x + vec2<i32>(0, 1));
+    let phi_x = 0.5 * (right - left);
+    let phi_y = 0.5 * (top - bottom);
+    let phi_xx = right - 2.0 * center + left;
+    let phi_yy = top - 2.0 * center + bottom;
+    let phi_xy = 0.25 * (levelset_at(x + vec2<i32>(1, 1)) - levelset_at(x + vec2<i32>(1, -1))
+        - levelset_at(x + vec2<i32>(-1, 1)) + levelset_at(x + vec2<i32>(-1, -1)));
+    let norm_squared = phi_x * phi_x + phi_y * phi_y;
+    if (norm_squared < 1.0e-6) {
+        return 0.0;
+    }
+    let kappa = (phi_xx * phi_y * phi_y - 2.0 * phi_x * phi_y * phi_xy + phi_yy * phi_x * phi_x)
+        / pow(norm_squared, 1.5);
+    return clamp(kappa, -1.0, 1.0) / constants.dx;
+}
+
+// Levelset of the cell x, clamped onto the grid.
+fn levelset_at(x: vec2<i32>) -> f32 {
+    let x_clamped = clamp(x, vec2<i32>(0), vec2<i32>(textureDimensions(levelset)) - 1);
+    return textureLoad(levelset, x_clamped).r;
+}
+
 fn gaussian_2d(x: vec2<f32>, x0: vec2<f32>, sigma: f32) -> f32 {
     let b = -1.0 / (2.0 * sigma * sigma);
     return exp(b * dot(x - x0, x - x0));
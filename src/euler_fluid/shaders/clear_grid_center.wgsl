@@ -0,0 +1,19 @@
+@group(0) @binding(0) var p0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(1) var p1: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(0) var div: texture_storage_2d<r32float, read_write>;
+
+@group(2) @binding(0) var seeds_x: texture_storage_2d<r32float, read_write>;
+@group(2) @binding(1) var seeds_y: texture_storage_2d<r32float, read_write>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn clear_grid_center(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = vec2<i32>(i32(global_id.x), i32(global_id.y));
+    textureStore(p0, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+    textureStore(p1, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+    textureStore(div, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+    // (-1, -1) indicates that no seed is found for jump flooding.
+    textureStore(seeds_x, x, vec4<f32>(-1.0, 0.0, 0.0, 0.0));
+    textureStore(seeds_y, x, vec4<f32>(-1.0, 0.0, 0.0, 0.0));
+}
@@ -0,0 +1,38 @@
+#define_import_path bevy_fluid::cut_cell
+
+struct Circle {
+    radius: f32,
+    center: vec2<f32>,
+    velocity: vec2<f32>,
+}
+
+// Signed distance from x to the circle in grid units. Negative inside the circle.
+fn circle_sdf(circle: Circle, x: vec2<f32>) -> f32 {
+    return distance(x, circle.center) - circle.radius;
+}
+
+// Fraction of the segment between two points lying outside the solid,
+// given the signed distances to the solid at the points.
+fn open_fraction(sdf0: f32, sdf1: f32) -> f32 {
+    if (sdf0 >= 0.0 && sdf1 >= 0.0) {
+        return 1.0;
+    }
+    if (sdf0 < 0.0 && sdf1 < 0.0) {
+        return 0.0;
+    }
+    return max(sdf0, sdf1) / abs(sdf0 - sdf1);
+}
+
+// Open fractions of the left, right, bottom and top faces of the cell x.
+fn face_fractions(
+    u_fraction: texture_storage_2d<r32float, read_write>,
+    v_fraction: texture_storage_2d<r32float, read_write>,
+    x: vec2<i32>,
+) -> vec4<f32> {
+    return vec4<f32>(
+        textureLoad(u_fraction, x).r,
+        textureLoad(u_fraction, x + vec2<i32>(1, 0)).r,
+        textureLoad(v_fraction, x).r,
+        textureLoad(v_fraction, x + vec2<i32>(0, 1)).r,
+    );
+}
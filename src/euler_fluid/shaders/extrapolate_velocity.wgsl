@@ -0,0 +1,111 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+
+// Extrapolates the velocity solved on the fluid faces into the air and solid faces, one layer of faces per dispatch.
+// A face is valid when the velocity on it is known. Each layer reads the valid flags of the previous layer and writes the next,
+// so the faces written in a layer never read each other.
+
+@group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(0) var u_valid: texture_storage_2d<r32uint, read_write>;
+@group(1) @binding(1) var v_valid: texture_storage_2d<r32uint, read_write>;
+@group(1) @binding(2) var u_valid_next: texture_storage_2d<r32uint, read_write>;
+@group(1) @binding(3) var v_valid_next: texture_storage_2d<r32uint, read_write>;
+
+@group(2) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(2) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(2) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
+
+@group(3) @binding(0) var<uniform> constants: SimulationUniform;
+
+// The faces updated by the pressure, which are open and next to a fluid cell, are valid.
+// In two-phase flow, the faces next to an air cell are updated by the pressure as well.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn initialize_valid_faces(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    let valid_u = textureLoad(u_fraction, x_u).r > 0.0
+        && (is_solved(x_u - vec2<i32>(1, 0)) || is_solved(x_u));
+    textureStore(u_valid, x_u, vec4<u32>(u32(valid_u), 0, 0, 0));
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    let valid_v = textureLoad(v_fraction, x_v).r > 0.0
+        && (is_solved(x_v - vec2<i32>(0, 1)) || is_solved(x_v));
+    textureStore(v_valid, x_v, vec4<u32>(u32(valid_v), 0, 0, 0));
+}
+
+// Sets the invalid faces next to valid ones to the average of the valid neighbors.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn extrapolate_velocity(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (all(x_u < vec2<i32>(textureDimensions(u0)))) {
+        var valid_u = textureLoad(u_valid, x_u).r;
+        if (valid_u == 0u) {
+            var sum = 0.0;
+            var count = 0u;
+            for (var i = 0; i < 4; i++) {
+                let x_neighbor = x_u + neighbor_offset(i);
+                if (is_valid(u_valid, x_neighbor)) {
+                    sum += textureLoad(u0, x_neighbor).r;
+                    count++;
+                }
+            }
+            if (count > 0u) {
+                textureStore(u0, x_u, vec4<f32>(sum / f32(count), 0.0, 0.0, 0.0));
+                valid_u = 1u;
+            }
+        }
+        textureStore(u_valid_next, x_u, vec4<u32>(valid_u, 0, 0, 0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (all(x_v < vec2<i32>(textureDimensions(v0)))) {
+        var valid_v = textureLoad(v_valid, x_v).r;
+        if (valid_v == 0u) {
+            var sum = 0.0;
+            var count = 0u;
+            for (var i = 0; i < 4; i++) {
+                let x_neighbor = x_v + neighbor_offset(i);
+                if (is_valid(v_valid, x_neighbor)) {
+                    sum += textureLoad(v0, x_neighbor).r;
+                    count++;
+                }
+            }
+            if (count > 0u) {
+                textureStore(v0, x_v, vec4<f32>(sum / f32(count), 0.0, 0.0, 0.0));
+                valid_v = 1u;
+            }
+        }
+        textureStore(v_valid_next, x_v, vec4<u32>(valid_v, 0, 0, 0));
+    }
+}
+
+fn neighbor_offset(i: i32) -> vec2<i32> {
+    switch (i) {
+        case 0: {
+            return vec2<i32>(-1, 0);
+        }
+        case 1: {
+            return vec2<i32>(1, 0);
+        }
+        case 2: {
+            return vec2<i32>(0, -1);
+        }
+        default: {
+            return vec2<i32>(0, 1);
+        }
+    }
+}
+
+fn is_valid(valid: texture_storage_2d<r32uint, read_write>, x: vec2<i32>) -> bool {
+    if (any(x < vec2<i32>(0)) || any(x >= vec2<i32>(textureDimensions(valid)))) {
+        return false;
+    }
+    return textureLoad(valid, x).r == 1u;
+}
+
+fn is_solved(x: vec2<i32>) -> bool {
+    let label = textureLoad(grid_label, x).r;
+    return label == 1u || (constants.air_rho > 0.0 && label == 0u);
+}
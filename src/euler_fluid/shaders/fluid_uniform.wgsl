@@ -6,4 +6,12 @@ struct SimulationUniform {
     rho: f32,
     gravity: vec2<f32>,
     initial_fluid_level: f32,
+    force_kernel_radius: f32,
+    sor_omega: f32,
+    pcg_tolerance: f32,
+    interpolation: u32,
+    vorticity_confinement: f32,
+    viscosity: f32,
+    air_rho: f32,
+    surface_tension: f32,
 }
\ No newline at end of file
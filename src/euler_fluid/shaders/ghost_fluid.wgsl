@@ -0,0 +1,142 @@
+#define_import_path bevy_fluid::ghost_fluid
+
+#import bevy_fluid::coordinate::{left, right, bottom, top};
+
+// Ghost fluid method for the free surface.
+// p = 0 is imposed where the levelset crosses 0 between a fluid cell and its empty neighbor,
+// instead of at the center of the empty cell.
+//
+// Two-phase flow.
+// When density_ratio, the density of the air divided by the density of the fluid, is positive,
+// the air cells are solved together with the fluid cells instead of imposing p = 0 on the interface,
+// and each face is weighted by the density of the fluid divided by the density on the face.
+
+// Lower bound of the interface fraction to keep the coefficients bounded.
+const MIN_INTERFACE_FRACTION: f32 = 0.01;
+
+// Fraction of the distance from the fluid cell center to the empty cell center at which the interface lies.
+fn interface_fraction(levelset_fluid: f32, levelset_empty: f32) -> f32 {
+    let denominator = levelset_fluid - levelset_empty;
+    if (denominator >= 0.0) {
+        return 1.0;
+    }
+    return clamp(levelset_fluid / denominator, MIN_INTERFACE_FRACTION, 1.0);
+}
+
+// Pressure extrapolated linearly from the fluid cell to the empty cell through p = 0 on the interface.
+fn ghost_pressure(p_fluid: f32, levelset_fluid: f32, levelset_empty: f32) -> f32 {
+    return p_fluid * (1.0 - 1.0 / interface_fraction(levelset_fluid, levelset_empty));
+}
+
+// Whether the pressure of x is solved, which is the fluid cells and also the air cells in two-phase flow.
+fn is_unknown(grid_label: texture_storage_2d<r32uint, read_write>, x: vec2<i32>, density_ratio: f32) -> bool {
+    let label = textureLoad(grid_label, x).r;
+    return label == 1u || (density_ratio > 0.0 && label == 0u);
+}
+
+// Fraction of the segment between two cell centers lying in the fluid.
+fn fluid_fraction(levelset0: f32, levelset1: f32) -> f32 {
+    let levelset_min = min(levelset0, levelset1);
+    let levelset_max = max(levelset0, levelset1);
+    if (levelset_max < 0.0) {
+        return 1.0;
+    }
+    if (levelset_min >= 0.0) {
+        return 0.0;
+    }
+    return levelset_min / (levelset_min - levelset_max);
+}
+
+// Density on the face between two cells relative to the density of the fluid. Always 1 for single-phase flow.
+fn face_density(levelset0: f32, levelset1: f32, density_ratio: f32) -> f32 {
+    if (density_ratio <= 0.0) {
+        return 1.0;
+    }
+    return mix(density_ratio, 1.0, fluid_fraction(levelset0, levelset1));
+}
+
+// Open fractions of the left, right, bottom and top faces of x divided by the relative densities of the faces,
+// which weight the neighbors in the pressure stencil.
+fn stencil_fractions(
+    levelset: texture_storage_2d<r32float, read_write>,
+    fractions: vec4<f32>,
+    x: vec2<i32>,
+    density_ratio: f32,
+) -> vec4<f32> {
+    if (density_ratio <= 0.0) {
+        return fractions;
+    }
+    let levelset_x = textureLoad(levelset, x).r;
+    return fractions / vec4<f32>(
+        face_density(levelset_x, textureLoad(levelset, left(x)).r, density_ratio),
+        face_density(levelset_x, textureLoad(levelset, right(x)).r, density_ratio),
+        face_density(levelset_x, textureLoad(levelset, bottom(x)).r, density_ratio),
+        face_density(levelset_x, textureLoad(levelset, top(x)).r, density_ratio),
+    );
+}
+
+// Weight of the neighbor on the diagonal of the pressure stencil of x.
+// 0 for solid, 1 for fluid, and 1 / (interface fraction) for empty neighbors of fluid cells.
+// 1 for all the non-solid neighbors in two-phase flow.
+fn neighbor_weight(
+    grid_label: texture_storage_2d<r32uint, read_write>,
+    levelset: texture_storage_2d<r32float, read_write>,
+    x: vec2<i32>,
+    x_neighbor: vec2<i32>,
+    density_ratio: f32,
+) -> f32 {
+    let label_neighbor = textureLoad(grid_label, x_neighbor).r;
+    if (label_neighbor == 2u) {
+        return 0.0;
+    }
+    if (density_ratio > 0.0) {
+        return 1.0;
+    }
+    if (label_neighbor == 0u && textureLoad(grid_label, x).r == 1u) {
+        let theta = interface_fraction(textureLoad(levelset, x).r, textureLoad(levelset, x_neighbor).r);
+        return 1.0 / theta;
+    }
+    return 1.0;
+}
+
+// Diagonal of the pressure stencil of x, weighted by the stencil fractions of the left, right, bottom and top faces.
+// The off-diagonals are minus the stencil fractions for the unknown neighbors.
+fn diagonal(
+    grid_label: texture_storage_2d<r32uint, read_write>,
+    levelset: texture_storage_2d<r32float, read_write>,
+    fractions: vec4<f32>,
+    x: vec2<i32>,
+    density_ratio: f32,
+) -> f32 {
+    return fractions.x * neighbor_weight(grid_label, levelset, x, left(x), density_ratio)
+        + fractions.y * neighbor_weight(grid_label, levelset, x, right(x), density_ratio)
+        + fractions.z * neighbor_weight(grid_label, levelset, x, bottom(x), density_ratio)
+        + fractions.w * neighbor_weight(grid_label, levelset, x, top(x), density_ratio);
+}
+
+// Pressures of the cells x0 and x1 on both sides of a face.
+// The pressure of an empty cell next to a fluid cell is replaced by the ghost pressure, unless in two-phase flow.
+fn face_pressures(
+    grid_label: texture_storage_2d<r32uint, read_write>,
+    levelset: texture_storage_2d<r32float, read_write>,
+    x0: vec2<i32>,
+    x1: vec2<i32>,
+    p0: f32,
+    p1: f32,
+    density_ratio: f32,
+) -> vec2<f32> {
+    if (density_ratio > 0.0) {
+        return vec2<f32>(p0, p1);
+    }
+    let label0 = textureLoad(grid_label, x0).r;
+    let label1 = textureLoad(grid_label, x1).r;
+    if (label0 == 1u && label1 == 0u) {
+        let levelset0 = textureLoad(levelset, x0).r;
+        return vec2<f32>(p0, ghost_pressure(p0, levelset0, textureLoad(levelset, x1).r));
+    }
+    if (label0 == 0u && label1 == 1u) {
+        let levelset1 = textureLoad(levelset, x1).r;
+        return vec2<f32>(ghost_pressure(p1, levelset1, textureLoad(levelset, x0).r), p1);
+    }
+    return vec2<f32>(p0, p1);
+}
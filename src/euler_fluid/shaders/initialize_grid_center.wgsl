@@ -6,7 +6,7 @@
 @group(1) @binding(0) var<uniform> constants: SimulationUniform;
 
 @compute
-@workgroup_size(8, 8, 1)
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
 fn initialize_grid_center(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let x = vec2<i32>(i32(global_id.x), i32(global_id.y));
     textureStore(grid_label, x, vec4<u32>(1, 0, 0, 0));
@@ -4,7 +4,7 @@
 @group(0) @binding(3) var v1: texture_storage_2d<r32float, read_write>;
 
 @compute
-@workgroup_size(1, 64, 1)
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
 fn initialize_velocity(
     @builtin(global_invocation_id) invocation_id: vec3<u32>,
 ) {
@@ -0,0 +1,143 @@
+#define_import_path bevy_fluid::interpolation
+
+// Interpolation of the fields stored on storage textures.
+// Positions passed to interpolate_u, interpolate_v and interpolate_center are in grid units
+// where the center of the cell (i, j) is at (i, j), u(i, j) at (i - 0.5, j) and v(i, j) at (i, j - 0.5).
+// Positions passed to the kernels are in texel coordinates. Positions outside the texture are clamped onto it.
+
+// Kernels. Mirrors Interpolation in definition.rs.
+const INTERPOLATION_BILINEAR: u32 = 0u;
+const INTERPOLATION_CATMULL_ROM: u32 = 1u;
+const INTERPOLATION_MONOTONE_CUBIC: u32 = 2u;
+
+fn interpolate_u(u: texture_storage_2d<r32float, read_write>, x: vec2<f32>, kernel: u32) -> f32 {
+    return interpolate(u, x + vec2<f32>(0.5, 0.0), kernel);
+}
+
+fn interpolate_v(v: texture_storage_2d<r32float, read_write>, x: vec2<f32>, kernel: u32) -> f32 {
+    return interpolate(v, x + vec2<f32>(0.0, 0.5), kernel);
+}
+
+fn interpolate_center(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>, kernel: u32) -> f32 {
+    return interpolate(grid, x, kernel);
+}
+
+// Departure point of the trajectory through x, traced back by dt with second order Runge-Kutta.
+fn departure(
+    u: texture_storage_2d<r32float, read_write>,
+    v: texture_storage_2d<r32float, read_write>,
+    x: vec2<f32>,
+    dt: f32,
+    kernel: u32,
+) -> vec2<f32> {
+    let velocity = vec2<f32>(interpolate_u(u, x, kernel), interpolate_v(v, x, kernel));
+    let x_mid = x - 0.5 * dt * velocity;
+    let velocity_mid = vec2<f32>(interpolate_u(u, x_mid, kernel), interpolate_v(v, x_mid, kernel));
+    return x - dt * velocity_mid;
+}
+
+fn interpolate(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>, kernel: u32) -> f32 {
+    switch (kernel) {
+        case INTERPOLATION_CATMULL_ROM: {
+            return catmull_rom(grid, x);
+        }
+        case INTERPOLATION_MONOTONE_CUBIC: {
+            return monotone_cubic(grid, x);
+        }
+        default: {
+            return bilinear(grid, x);
+        }
+    }
+}
+
+fn bilinear(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>) -> f32 {
+    let x_clamped = clamp_texel(grid, x);
+    let i = vec2<i32>(floor(x_clamped));
+    let f = x_clamped - vec2<f32>(i);
+    let g00 = texel(grid, i);
+    let g10 = texel(grid, i + vec2<i32>(1, 0));
+    let g01 = texel(grid, i + vec2<i32>(0, 1));
+    let g11 = texel(grid, i + vec2<i32>(1, 1));
+    return mix(mix(g00, g10, f.x), mix(g01, g11, f.x), f.y);
+}
+
+// Minimum and maximum of the 4 texels around x, which bound the bilinear interpolation.
+fn texel_range(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>) -> vec2<f32> {
+    let i = vec2<i32>(floor(clamp_texel(grid, x)));
+    let g00 = texel(grid, i);
+    let g10 = texel(grid, i + vec2<i32>(1, 0));
+    let g01 = texel(grid, i + vec2<i32>(0, 1));
+    let g11 = texel(grid, i + vec2<i32>(1, 1));
+    return vec2<f32>(min(min(g00, g10), min(g01, g11)), max(max(g00, g10), max(g01, g11)));
+}
+
+// Catmull-Rom spline over the 4x4 texels around x. Sharper than bilinear but may overshoot.
+fn catmull_rom(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>) -> f32 {
+    let x_clamped = clamp_texel(grid, x);
+    let i = vec2<i32>(floor(x_clamped));
+    let f = x_clamped - vec2<f32>(i);
+    var rows: vec4<f32>;
+    for (var j = -1; j < 3; j++) {
+        rows[j + 1] = catmull_rom_1d(row(grid, i + vec2<i32>(0, j)), f.x);
+    }
+    return catmull_rom_1d(rows, f.y);
+}
+
+// Cubic Hermite spline whose slopes are zeroed where the data is not monotone,
+// so that the result stays within the 2x2 texels around x.
+fn monotone_cubic(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>) -> f32 {
+    let x_clamped = clamp_texel(grid, x);
+    let i = vec2<i32>(floor(x_clamped));
+    let f = x_clamped - vec2<f32>(i);
+    var rows: vec4<f32>;
+    for (var j = -1; j < 3; j++) {
+        rows[j + 1] = monotone_cubic_1d(row(grid, i + vec2<i32>(0, j)), f.x);
+    }
+    let range = texel_range(grid, x);
+    return clamp(monotone_cubic_1d(rows, f.y), range.x, range.y);
+}
+
+// Interpolates between p.y and p.z at t in [0, 1].
+fn catmull_rom_1d(p: vec4<f32>, t: f32) -> f32 {
+    return hermite(p.y, p.z, 0.5 * (p.z - p.x), 0.5 * (p.w - p.y), t);
+}
+
+fn monotone_cubic_1d(p: vec4<f32>, t: f32) -> f32 {
+    let delta = p.z - p.y;
+    var d0 = 0.5 * (p.z - p.x);
+    var d1 = 0.5 * (p.w - p.y);
+    if (delta == 0.0 || sign(d0) != sign(delta) || sign(d1) != sign(delta)) {
+        d0 = 0.0;
+        d1 = 0.0;
+    }
+    return hermite(p.y, p.z, d0, d1, t);
+}
+
+fn hermite(p0: f32, p1: f32, d0: f32, d1: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    return (2.0 * t3 - 3.0 * t2 + 1.0) * p0
+        + (t3 - 2.0 * t2 + t) * d0
+        + (-2.0 * t3 + 3.0 * t2) * p1
+        + (t3 - t2) * d1;
+}
+
+// The 4 texels from i - (1, 0) to i + (2, 0).
+fn row(grid: texture_storage_2d<r32float, read_write>, i: vec2<i32>) -> vec4<f32> {
+    return vec4<f32>(
+        texel(grid, i - vec2<i32>(1, 0)),
+        texel(grid, i),
+        texel(grid, i + vec2<i32>(1, 0)),
+        texel(grid, i + vec2<i32>(2, 0)),
+    );
+}
+
+fn clamp_texel(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>) -> vec2<f32> {
+    return clamp(x, vec2<f32>(0.0), vec2<f32>(textureDimensions(grid)) - 1.0);
+}
+
+// Texel at i, clamped onto the texture.
+fn texel(grid: texture_storage_2d<r32float, read_write>, i: vec2<i32>) -> f32 {
+    let i_clamped = clamp(i, vec2<i32>(0), vec2<i32>(textureDimensions(grid)) - 1);
+    return textureLoad(grid, i_clamped).r;
+}
@@ -1,5 +1,7 @@
 #import bevy_fluid::fluid_uniform::SimulationUniform;
 #import bevy_fluid::coordinate::{left, right, bottom, top};
+#import bevy_fluid::cut_cell::face_fractions;
+#import bevy_fluid::ghost_fluid::{diagonal, is_unknown, stencil_fractions};
 
 @group(0) @binding(0) var<uniform> constants: SimulationUniform;
 
@@ -8,10 +10,13 @@
 
 @group(2) @binding(0) var div: texture_storage_2d<r32float, read_write>;
 
+@group(3) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
 @group(3) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(3) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
 
 @compute
-@workgroup_size(8, 8, 1)
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
 fn jacobi_iteration(
     @builtin(global_invocation_id) invocation_id: vec3<u32>
 ) {
@@ -22,20 +27,18 @@ fn jacobi_iteration(
         return;
     }
     
-    let coef = 4.0 
-        - is_solid(grid_label, left(x))
-        - is_solid(grid_label, right(x))
-        - is_solid(grid_label, bottom(x))
-        - is_solid(grid_label, top(x));
+    let density_ratio = constants.air_rho / constants.rho;
+    let fractions = stencil_fractions(levelset, face_fractions(u_fraction, v_fraction, x), x, density_ratio);
+    let coef = diagonal(grid_label, levelset, fractions, x, density_ratio);
 
     if (coef == 0.0) {
         textureStore(p1, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
         return;
     } else {
-        let p_left = pij(p0, grid_label, left(x));
-        let p_right = pij(p0, grid_label, right(x));
-        let p_bottom = pij(p0, grid_label, bottom(x));
-        let p_top = pij(p0, grid_label, top(x));
+        let p_left = fractions.x * pij(p0, grid_label, left(x), density_ratio);
+        let p_right = fractions.y * pij(p0, grid_label, right(x), density_ratio);
+        let p_bottom = fractions.z * pij(p0, grid_label, bottom(x), density_ratio);
+        let p_top = fractions.w * pij(p0, grid_label, top(x), density_ratio);
         let div_ij = textureLoad(div, x).r;
 
         let factor = constants.dx * constants.rho / constants.dt;
@@ -45,7 +48,7 @@ fn jacobi_iteration(
 }
 
 @compute
-@workgroup_size(8, 8, 1)
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
 fn jacobi_iteration_reverse(
     @builtin(global_invocation_id) invocation_id: vec3<u32>
 ) {
@@ -56,20 +59,18 @@ fn jacobi_iteration_reverse(
         return;
     }
     
-    let coef = 4.0 
-        - is_solid(grid_label, left(x))
-        - is_solid(grid_label, right(x))
-        - is_solid(grid_label, bottom(x))
-        - is_solid(grid_label, top(x));
+    let density_ratio = constants.air_rho / constants.rho;
+    let fractions = stencil_fractions(levelset, face_fractions(u_fraction, v_fraction, x), x, density_ratio);
+    let coef = diagonal(grid_label, levelset, fractions, x, density_ratio);
 
     if (coef == 0.0) {
         textureStore(p0, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
         return;
     } else {
-        let p_left = pij(p1, grid_label, left(x));
-        let p_right = pij(p1, grid_label, right(x));
-        let p_bottom = pij(p1, grid_label, bottom(x));
-        let p_top = pij(p1, grid_label, top(x));
+        let p_left = fractions.x * pij(p1, grid_label, left(x), density_ratio);
+        let p_right = fractions.y * pij(p1, grid_label, right(x), density_ratio);
+        let p_bottom = fractions.z * pij(p1, grid_label, bottom(x), density_ratio);
+        let p_top = fractions.w * pij(p1, grid_label, top(x), density_ratio);
         let div_ij = textureLoad(div, x).r;
 
         let factor = constants.dx * constants.rho / constants.dt;
@@ -78,22 +79,11 @@ fn jacobi_iteration_reverse(
     }
 }
 
-fn pij(p: texture_storage_2d<r32float, read_write>, label: texture_storage_2d<r32uint, read_write>, x: vec2<i32>) -> f32 {
-    return textureLoad(p, x).r * is_fluid(label, x);
+fn pij(
+    p: texture_storage_2d<r32float, read_write>,
+    label: texture_storage_2d<r32uint, read_write>,
+    x: vec2<i32>,
+    density_ratio: f32,
+) -> f32 {
+    return select(0.0, textureLoad(p, x).r, is_unknown(label, x, density_ratio));
 }
-
-fn is_solid(label: texture_storage_2d<r32uint, read_write>, x: vec2<i32>) -> f32 {
-    if (textureLoad(label, x).r == 2) {
-        return 1.0;
-    } else {
-        return 0.0;
-    }
-}
-
-fn is_fluid(label: texture_storage_2d<r32uint, read_write>, x: vec2<i32>) -> f32 {
-    if (textureLoad(label, x).r == 1) {
-        return 1.0;
-    } else {
-        return 0.0;
-    }
-}
\ No newline at end of file
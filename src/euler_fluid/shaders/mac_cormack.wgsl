@@ -0,0 +1,191 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+#import bevy_fluid::interpolation::{departure as trace_back, interpolate, texel_range};
+
+// MacCormack advection.
+// The field is advected forward, the result is advected backward, and half of the difference
+// between the original field and the round trip is added to the forward result as an estimate of its error.
+// The corrected value is limited to the range of the original values around the departure point.
+// Positions are in grid units where the center of the cell (i, j) is at (i, j).
+
+@group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(2) var u1: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(3) var v1: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+
+@group(2) @binding(0) var<uniform> constants: SimulationUniform;
+
+@group(3) @binding(0) var u_backward: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(1) var v_backward: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(2) var levelset_forward: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(3) var levelset_backward: texture_storage_2d<r32float, read_write>;
+
+// Offsets of the positions of the faces and the cells from the texel coordinates.
+const U_OFFSET: vec2<f32> = vec2<f32>(-0.5, 0.0);
+const V_OFFSET: vec2<f32> = vec2<f32>(0.0, -0.5);
+const CENTER_OFFSET: vec2<f32> = vec2<f32>(0.0, 0.0);
+
+// u1 = u0 advected forward.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn mac_cormack_velocity_forward(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u)) {
+        var u = 0.0;
+        if (!is_air_face(x_u, vec2<i32>(1, 0))) {
+            let x = departure(vec2<f32>(x_u) + U_OFFSET, constants.dt);
+            u = sample(u0, x, U_OFFSET).x;
+        }
+        textureStore(u1, x_u, vec4<f32>(u, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v)) {
+        var v = 0.0;
+        if (!is_air_face(x_v, vec2<i32>(0, 1))) {
+            let x = departure(vec2<f32>(x_v) + V_OFFSET, constants.dt);
+            v = sample(v0, x, V_OFFSET).x;
+        }
+        textureStore(v1, x_v, vec4<f32>(v, 0.0, 0.0, 0.0));
+    }
+}
+
+// u_backward = u1 advected backward.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn mac_cormack_velocity_backward(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u)) {
+        let x = departure(vec2<f32>(x_u) + U_OFFSET, -constants.dt);
+        textureStore(u_backward, x_u, vec4<f32>(sample(u1, x, U_OFFSET).x, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v)) {
+        let x = departure(vec2<f32>(x_v) + V_OFFSET, -constants.dt);
+        textureStore(v_backward, x_v, vec4<f32>(sample(v1, x, V_OFFSET).x, 0.0, 0.0, 0.0));
+    }
+}
+
+// u1 += (u0 - u_backward) / 2, limited. Each face reads and writes only itself on u1.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn mac_cormack_velocity_correct(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u) && !is_air_face(x_u, vec2<i32>(1, 0))) {
+        let x = departure(vec2<f32>(x_u) + U_OFFSET, constants.dt);
+        let u = correct(
+            textureLoad(u1, x_u).r,
+            textureLoad(u0, x_u).r,
+            textureLoad(u_backward, x_u).r,
+            sample(u0, x, U_OFFSET),
+        );
+        textureStore(u1, x_u, vec4<f32>(u, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v) && !is_air_face(x_v, vec2<i32>(0, 1))) {
+        let x = departure(vec2<f32>(x_v) + V_OFFSET, constants.dt);
+        let v = correct(
+            textureLoad(v1, x_v).r,
+            textureLoad(v0, x_v).r,
+            textureLoad(v_backward, x_v).r,
+            sample(v0, x, V_OFFSET),
+        );
+        textureStore(v1, x_v, vec4<f32>(v, 0.0, 0.0, 0.0));
+    }
+}
+
+// levelset_forward = levelset advected forward.
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn mac_cormack_levelset_forward(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_cell = vec2<i32>(invocation_id.xy);
+    if (!in_texture(levelset, x_cell)) {
+        return;
+    }
+    let x = departure(vec2<f32>(x_cell), constants.dt);
+    let value = sample(levelset, x, CENTER_OFFSET).x;
+    textureStore(levelset_forward, x_cell, vec4<f32>(value, 0.0, 0.0, 0.0));
+}
+
+// levelset_backward = levelset_forward advected backward.
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn mac_cormack_levelset_backward(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_cell = vec2<i32>(invocation_id.xy);
+    if (!in_texture(levelset, x_cell)) {
+        return;
+    }
+    let x = departure(vec2<f32>(x_cell), -constants.dt);
+    let value = sample(levelset_forward, x, CENTER_OFFSET).x;
+    textureStore(levelset_backward, x_cell, vec4<f32>(value, 0.0, 0.0, 0.0));
+}
+
+// levelset_backward = levelset_forward + (levelset - levelset_backward) / 2, limited.
+// The result is kept aside because the limiter reads levelset around the departure point.
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn mac_cormack_levelset_correct(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_cell = vec2<i32>(invocation_id.xy);
+    if (!in_texture(levelset, x_cell)) {
+        return;
+    }
+    let x = departure(vec2<f32>(x_cell), constants.dt);
+    let value = correct(
+        textureLoad(levelset_forward, x_cell).r,
+        textureLoad(levelset, x_cell).r,
+        textureLoad(levelset_backward, x_cell).r,
+        sample(levelset, x, CENTER_OFFSET),
+    );
+    textureStore(levelset_backward, x_cell, vec4<f32>(value, 0.0, 0.0, 0.0));
+}
+
+// levelset = levelset_backward.
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn mac_cormack_levelset_copy(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_cell = vec2<i32>(invocation_id.xy);
+    if (!in_texture(levelset, x_cell)) {
+        return;
+    }
+    textureStore(levelset, x_cell, textureLoad(levelset_backward, x_cell));
+}
+
+// forward + (original - backward) / 2, clamped to the range of the original values around the departure point.
+fn correct(forward: f32, original: f32, backward: f32, original_sample: vec3<f32>) -> f32 {
+    let corrected = forward + 0.5 * (original - backward);
+    return clamp(corrected, original_sample.y, original_sample.z);
+}
+
+fn departure(x: vec2<f32>, dt: f32) -> vec2<f32> {
+    return trace_back(u0, v0, x, dt, constants.interpolation);
+}
+
+// Interpolation of the texture whose texel (i, j) is at (i, j) + offset,
+// and the minimum and maximum of the 4 texels around x.
+fn sample(grid: texture_storage_2d<r32float, read_write>, x: vec2<f32>, offset: vec2<f32>) -> vec3<f32> {
+    let x_texel = x - offset;
+    let range = texel_range(grid, x_texel);
+    return vec3<f32>(interpolate(grid, x_texel, constants.interpolation), range);
+}
+
+fn in_texture(grid: texture_storage_2d<r32float, read_write>, x: vec2<i32>) -> bool {
+    return all(x >= vec2<i32>(0)) && all(x < vec2<i32>(textureDimensions(grid)));
+}
+
+// Whether the face x between the cells x - direction and x lies in the air, where the velocity is cleared as in advection.wgsl.
+// The air is advected as well in two-phase flow.
+fn is_air_face(x: vec2<i32>, direction: vec2<i32>) -> bool {
+    return constants.air_rho == 0.0 && label(x - direction) == 0u && label(x) == 0u;
+}
+
+// Cells out of the grid are solid.
+fn label(x: vec2<i32>) -> u32 {
+    if (any(x < vec2<i32>(0)) || any(x >= vec2<i32>(textureDimensions(grid_label)))) {
+        return 2u;
+    }
+    return textureLoad(grid_label, x).r;
+}
@@ -0,0 +1,36 @@
+@group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
+
+// Bit patterns of the reductions. The order of non-negative floats is preserved as u32.
+// [0]: max(|u|, |v|), [1]: residual written by residual.wgsl,
+// [2]: iterations of PCG copied from pcg.wgsl.
+@group(1) @binding(0) var<storage, read_write> reduction: array<atomic<u32>, 3>;
+
+var<workgroup> workgroup_max: atomic<u32>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn reduce_max_velocity(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&workgroup_max, 0u);
+    }
+    workgroupBarrier();
+
+    let x = vec2<i32>(invocation_id.xy);
+    var speed = 0.0;
+    if (all(x < vec2<i32>(textureDimensions(u0)))) {
+        speed = max(speed, abs(textureLoad(u0, x).r));
+    }
+    if (all(x < vec2<i32>(textureDimensions(v0)))) {
+        speed = max(speed, abs(textureLoad(v0, x).r));
+    }
+    atomicMax(&workgroup_max, bitcast<u32>(speed));
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        atomicMax(&reduction[0], atomicLoad(&workgroup_max));
+    }
+}
@@ -0,0 +1,191 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+#import bevy_fluid::coordinate::{left, right, bottom, top};
+#import bevy_fluid::cut_cell::face_fractions;
+#import bevy_fluid::ghost_fluid::{diagonal, stencil_fractions};
+
+// Each level solves coef * p - (sum of p on the fluid neighbors) = rhs,
+// where coef is 4 minus the number of the solid neighbors.
+// On the finest level, rhs is the divergence, the right hand side is -dx * rho / dt * div,
+// coef is the diagonal of the ghost fluid method with the levelset,
+// and the neighbors are weighted by the open fractions of the faces.
+// In two-phase flow, the finest level solves the air cells as well with the faces weighted by the densities,
+// while the coarse levels correct the fluid cells only, keeping the air cells at 0 as in single-phase flow.
+
+@group(0) @binding(0) var<uniform> constants: SimulationUniform;
+
+@group(1) @binding(0) var p: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(1) var rhs: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(2) var label: texture_storage_2d<r32uint, read_write>;
+
+@group(2) @binding(0) var p_coarse: texture_storage_2d<r32float, read_write>;
+@group(2) @binding(1) var rhs_coarse: texture_storage_2d<r32float, read_write>;
+@group(2) @binding(2) var label_coarse: texture_storage_2d<r32uint, read_write>;
+
+@group(3) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn restrict_finest(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    let factor = constants.dx * constants.rho / constants.dt;
+    restrict_residual(vec2<i32>(invocation_id.xy), -factor, true);
+}
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn restrict_coarse(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    restrict_residual(vec2<i32>(invocation_id.xy), 1.0, false);
+}
+
+// Sums the residuals of the 2x2 cells into the coarse cell and coarsens the labels.
+// A coarse cell is fluid if any of the cells is fluid, and solid only if all of them are solid.
+// The coarse pressure is the correction to solve for, starting from 0.
+fn restrict_residual(x_coarse: vec2<i32>, rhs_scale: f32, finest: bool) {
+    if (any(x_coarse >= vec2<i32>(textureDimensions(label_coarse)))) {
+        return;
+    }
+    var residual_sum = 0.0;
+    var has_fluid = false;
+    var has_empty = false;
+    var density_ratio = 0.0;
+    if (finest) {
+        density_ratio = constants.air_rho / constants.rho;
+    }
+    for (var i = 0; i < 4; i++) {
+        let x = 2 * x_coarse + vec2<i32>(i % 2, i / 2);
+        let label_ij = textureLoad(label, x).r;
+        if (label_ij == 1) {
+            has_fluid = true;
+            var coef = coefficient(x);
+            var fractions = vec4<f32>(1.0);
+            if (finest) {
+                fractions = stencil_fractions(levelset, face_fractions(u_fraction, v_fraction, x), x, density_ratio);
+                coef = diagonal(label, levelset, fractions, x, density_ratio);
+            }
+            residual_sum += residual(x, rhs_scale, coef, fractions, density_ratio);
+        } else if (label_ij == 0) {
+            has_empty = true;
+        }
+    }
+
+    var label_coarse_ij = 2u;
+    if (has_fluid) {
+        label_coarse_ij = 1u;
+    } else if (has_empty) {
+        label_coarse_ij = 0u;
+    }
+    textureStore(label_coarse, x_coarse, vec4<u32>(label_coarse_ij, 0, 0, 0));
+    textureStore(rhs_coarse, x_coarse, vec4<f32>(residual_sum, 0.0, 0.0, 0.0));
+    textureStore(p_coarse, x_coarse, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+}
+
+// Adds the correction of the coarse cell to the fluid cells.
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn prolongate(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    if (any(x >= vec2<i32>(textureDimensions(label)))) {
+        return;
+    }
+    if (textureLoad(label, x).r != 1) {
+        return;
+    }
+    let correction = textureLoad(p_coarse, x / 2).r;
+    let p_ij = textureLoad(p, x).r;
+    textureStore(p, x, vec4<f32>(p_ij + correction, 0.0, 0.0, 0.0));
+}
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn smooth_red(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    gauss_seidel(vec2<i32>(invocation_id.xy), 0);
+}
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn smooth_black(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    gauss_seidel(vec2<i32>(invocation_id.xy), 1);
+}
+
+// Red-black Gauss-Seidel on a coarse level. The finest level is smoothed by red_black_sor.wgsl.
+fn gauss_seidel(x: vec2<i32>, parity: i32) {
+    if (any(x >= vec2<i32>(textureDimensions(label))) || (x.x + x.y) % 2 != parity) {
+        return;
+    }
+    if (textureLoad(label, x).r != 1) {
+        textureStore(p, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+        return;
+    }
+    let coef = coefficient(x);
+    if (coef == 0.0) {
+        textureStore(p, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+        return;
+    }
+    let p_ij = (neighbor_sum(x, vec4<f32>(1.0), 0.0) + textureLoad(rhs, x).r) / coef;
+    textureStore(p, x, vec4<f32>(p_ij, 0.0, 0.0, 0.0));
+}
+
+fn residual(x: vec2<i32>, rhs_scale: f32, coef: f32, fractions: vec4<f32>, density_ratio: f32) -> f32 {
+    let rhs_ij = rhs_scale * textureLoad(rhs, x).r;
+    return rhs_ij - (coef * textureLoad(p, x).r - neighbor_sum(x, fractions, density_ratio));
+}
+
+fn coefficient(x: vec2<i32>) -> f32 {
+    return 4.0
+        - is_solid(left(x))
+        - is_solid(right(x))
+        - is_solid(bottom(x))
+        - is_solid(top(x));
+}
+
+fn neighbor_sum(x: vec2<i32>, fractions: vec4<f32>, density_ratio: f32) -> f32 {
+    return dot(
+        fractions,
+        vec4<f32>(
+            pij(left(x), density_ratio),
+            pij(right(x), density_ratio),
+            pij(bottom(x), density_ratio),
+            pij(top(x), density_ratio),
+        ),
+    );
+}
+
+fn pij(x: vec2<i32>, density_ratio: f32) -> f32 {
+    return textureLoad(p, x).r * is_unknown(x, density_ratio);
+}
+
+// Cells out of the level are solid.
+fn is_solid(x: vec2<i32>) -> f32 {
+    if (any(x < vec2<i32>(0)) || any(x >= vec2<i32>(textureDimensions(label)))) {
+        return 1.0;
+    }
+    if (textureLoad(label, x).r == 2) {
+        return 1.0;
+    } else {
+        return 0.0;
+    }
+}
+
+// Fluid cells, and also air cells in two-phase flow.
+fn is_unknown(x: vec2<i32>, density_ratio: f32) -> f32 {
+    if (any(x < vec2<i32>(0)) || any(x >= vec2<i32>(textureDimensions(label)))) {
+        return 0.0;
+    }
+    let label_ij = textureLoad(label, x).r;
+    if (label_ij == 1 || (density_ratio > 0.0 && label_ij == 0)) {
+        return 1.0;
+    } else {
+        return 0.0;
+    }
+}
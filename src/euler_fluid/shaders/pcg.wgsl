@@ -0,0 +1,351 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+#import bevy_fluid::coordinate::{left, right, bottom, top};
+#import bevy_fluid::cut_cell::face_fractions;
+#import bevy_fluid::ghost_fluid::{diagonal, is_unknown, stencil_fractions};
+
+// Preconditioned conjugate gradient on the same system as jacobi_iteration.wgsl:
+// coef * p - (sum of p on the fluid neighbors) = -dx * rho / dt * div on the fluid cells,
+// where coef is the diagonal of the ghost fluid method and the neighbors are weighted by the open fractions of the faces.
+// In two-phase flow, the air cells are solved as well and the faces are also weighted by the densities.
+// Dot products are summed per workgroup into partials, then by a single workgroup into scalars.
+// Once the residual falls below the tolerance, the remaining iterations leave everything untouched.
+
+struct PcgCell {
+    r: f32,
+    z: f32,
+    s: f32,
+    q: f32,
+    t: f32,
+}
+
+struct PcgScalars {
+    sigma: f32,
+    alpha: f32,
+    beta: f32,
+    residual: f32,
+    iterations: u32,
+    converged: u32,
+}
+
+@group(0) @binding(0) var<uniform> constants: SimulationUniform;
+
+@group(1) @binding(0) var p: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(1) var div: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(2) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(1) @binding(3) var<storage, read_write> cells: array<PcgCell>;
+// x: sum of a dot product, y: maximum of |r|.
+@group(1) @binding(4) var<storage, read_write> partials: array<vec2<f32>>;
+@group(1) @binding(5) var<storage, read_write> scalars: PcgScalars;
+@group(1) @binding(6) var levelset: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(7) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(8) var v_fraction: texture_storage_2d<r32float, read_write>;
+
+const WORKGROUP_LENGTH: u32 = u32(#{WORKGROUP_SIZE} * #{WORKGROUP_SIZE});
+
+var<workgroup> workgroup_values: array<vec2<f32>, WORKGROUP_LENGTH>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_initialize(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    var value = vec2<f32>(0.0, 0.0);
+    if (is_fluid(x)) {
+        let factor = constants.dx * constants.rho / constants.dt;
+        let b = -factor * textureLoad(div, x).r;
+        let r = b - (coefficient(x) * textureLoad(p, x).r - fluid_sum_p(x));
+        cells[index(x)].r = r;
+        value.y = abs(r);
+    } else if (in_grid(x)) {
+        cells[index(x)] = PcgCell(0.0, 0.0, 0.0, 0.0, 0.0);
+    }
+
+    let total = reduce_workgroup(local_index, value);
+    if (local_index == 0u) {
+        partials[workgroup_index(workgroup_id, num_workgroups)].y = total.y;
+    }
+}
+
+// z = r / coef
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_precondition_jacobi(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    var value = vec2<f32>(0.0, 0.0);
+    if (is_active() && is_fluid(x)) {
+        let i = index(x);
+        let r = cells[i].r;
+        let z = r / coefficient(x);
+        cells[i].z = z;
+        value.x = r * z;
+    }
+
+    let total = reduce_workgroup(local_index, value);
+    if (local_index == 0u) {
+        partials[workgroup_index(workgroup_id, num_workgroups)].x = total.x;
+    }
+}
+
+// Incomplete Poisson preconditioner, z = (I - L D^-1) (I - D^-1 L^T) r,
+// where L is the strictly lower part and D the diagonal of the matrix.
+// t = (I - D^-1 L^T) r
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_precondition_incomplete_poisson_upper(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    if (!is_active() || !is_fluid(x)) {
+        return;
+    }
+    var upper = 0.0;
+    let fractions = weights(x);
+    if (is_fluid(right(x))) {
+        upper += fractions.y * cells[index(right(x))].r;
+    }
+    if (is_fluid(top(x))) {
+        upper += fractions.w * cells[index(top(x))].r;
+    }
+    let i = index(x);
+    cells[i].t = cells[i].r + upper / coefficient(x);
+}
+
+// z = (I - L D^-1) t
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_precondition_incomplete_poisson_lower(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    var value = vec2<f32>(0.0, 0.0);
+    if (is_active() && is_fluid(x)) {
+        var lower = 0.0;
+        let fractions = weights(x);
+        if (is_fluid(left(x))) {
+            lower += fractions.x * cells[index(left(x))].t / coefficient(left(x));
+        }
+        if (is_fluid(bottom(x))) {
+            lower += fractions.z * cells[index(bottom(x))].t / coefficient(bottom(x));
+        }
+        let i = index(x);
+        let z = cells[i].t + lower;
+        cells[i].z = z;
+        value.x = cells[i].r * z;
+    }
+
+    let total = reduce_workgroup(local_index, value);
+    if (local_index == 0u) {
+        partials[workgroup_index(workgroup_id, num_workgroups)].x = total.x;
+    }
+}
+
+// sigma = r . z
+@compute
+@workgroup_size(#{WORKGROUP_SIZE} * #{WORKGROUP_SIZE}, 1, 1)
+fn pcg_reduce_initial(@builtin(local_invocation_index) local_index: u32) {
+    let total = sum_partials(local_index);
+    if (local_index == 0u) {
+        scalars.sigma = total.x;
+        scalars.alpha = 0.0;
+        scalars.beta = 0.0;
+        scalars.residual = total.y;
+        scalars.iterations = 0u;
+        scalars.converged = select(0u, 1u, total.y <= tolerance());
+    }
+}
+
+// s = z + beta * s
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_update_direction(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x = vec2<i32>(invocation_id.xy);
+    if (!is_active() || !is_fluid(x)) {
+        return;
+    }
+    let i = index(x);
+    let s = select(cells[i].s, 0.0, scalars.iterations == 0u);
+    cells[i].s = cells[i].z + scalars.beta * s;
+}
+
+// q = A s
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_apply(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    var value = vec2<f32>(0.0, 0.0);
+    if (is_active() && is_fluid(x)) {
+        let i = index(x);
+        let s = cells[i].s;
+        let q = coefficient(x) * s - fluid_sum_s(x);
+        cells[i].q = q;
+        value.x = s * q;
+    }
+
+    let total = reduce_workgroup(local_index, value);
+    if (local_index == 0u) {
+        partials[workgroup_index(workgroup_id, num_workgroups)].x = total.x;
+    }
+}
+
+// alpha = sigma / (s . q)
+@compute
+@workgroup_size(#{WORKGROUP_SIZE} * #{WORKGROUP_SIZE}, 1, 1)
+fn pcg_reduce_alpha(@builtin(local_invocation_index) local_index: u32) {
+    let total = sum_partials(local_index);
+    if (local_index == 0u && scalars.converged == 0u) {
+        if (total.x == 0.0) {
+            scalars.converged = 1u;
+        } else {
+            scalars.alpha = scalars.sigma / total.x;
+        }
+    }
+}
+
+// p += alpha * s, r -= alpha * q
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn pcg_update(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let x = vec2<i32>(invocation_id.xy);
+    var value = vec2<f32>(0.0, 0.0);
+    if (is_active() && is_fluid(x)) {
+        let i = index(x);
+        let alpha = scalars.alpha;
+        let p_ij = textureLoad(p, x).r + alpha * cells[i].s;
+        textureStore(p, x, vec4<f32>(p_ij, 0.0, 0.0, 0.0));
+        let r = cells[i].r - alpha * cells[i].q;
+        cells[i].r = r;
+        value.y = abs(r);
+    }
+
+    let total = reduce_workgroup(local_index, value);
+    if (local_index == 0u) {
+        partials[workgroup_index(workgroup_id, num_workgroups)].y = total.y;
+    }
+}
+
+// beta = (r . z) / sigma, sigma = r . z
+@compute
+@workgroup_size(#{WORKGROUP_SIZE} * #{WORKGROUP_SIZE}, 1, 1)
+fn pcg_reduce_beta(@builtin(local_invocation_index) local_index: u32) {
+    let total = sum_partials(local_index);
+    if (local_index == 0u && scalars.converged == 0u) {
+        scalars.iterations += 1u;
+        scalars.residual = total.y;
+        if (total.y <= tolerance() || scalars.sigma == 0.0) {
+            scalars.converged = 1u;
+        } else {
+            scalars.beta = total.x / scalars.sigma;
+            scalars.sigma = total.x;
+        }
+    }
+}
+
+// Tolerance of max |r|. The divergence left by the residual r is r * dt / (dx^2 * rho).
+fn tolerance() -> f32 {
+    return constants.pcg_tolerance * constants.dx * constants.dx * constants.rho / constants.dt;
+}
+
+fn is_active() -> bool {
+    return scalars.converged == 0u;
+}
+
+// Sums x and takes the maximum of y over the workgroup. The result is valid on the first invocation.
+// Must be called in uniform control flow.
+fn reduce_workgroup(local_index: u32, value: vec2<f32>) -> vec2<f32> {
+    workgroup_values[local_index] = value;
+    workgroupBarrier();
+    var stride = 1u;
+    while (stride < WORKGROUP_LENGTH) {
+        stride *= 2u;
+    }
+    for (stride /= 2u; stride > 0u; stride /= 2u) {
+        if (local_index < stride && local_index + stride < WORKGROUP_LENGTH) {
+            let a = workgroup_values[local_index];
+            let b = workgroup_values[local_index + stride];
+            workgroup_values[local_index] = vec2<f32>(a.x + b.x, max(a.y, b.y));
+        }
+        workgroupBarrier();
+    }
+    return workgroup_values[0];
+}
+
+fn sum_partials(local_index: u32) -> vec2<f32> {
+    var value = vec2<f32>(0.0, 0.0);
+    for (var i = local_index; i < arrayLength(&partials); i += WORKGROUP_LENGTH) {
+        let partial = partials[i];
+        value = vec2<f32>(value.x + partial.x, max(value.y, partial.y));
+    }
+    return reduce_workgroup(local_index, value);
+}
+
+fn workgroup_index(workgroup_id: vec3<u32>, num_workgroups: vec3<u32>) -> u32 {
+    return workgroup_id.y * num_workgroups.x + workgroup_id.x;
+}
+
+fn in_grid(x: vec2<i32>) -> bool {
+    return all(x >= vec2<i32>(0)) && all(x < vec2<i32>(textureDimensions(grid_label)));
+}
+
+fn index(x: vec2<i32>) -> u32 {
+    return u32(x.y) * textureDimensions(grid_label).x + u32(x.x);
+}
+
+// Fluid cells, and also air cells in two-phase flow.
+fn is_fluid(x: vec2<i32>) -> bool {
+    return in_grid(x) && is_unknown(grid_label, x, density_ratio());
+}
+
+fn coefficient(x: vec2<i32>) -> f32 {
+    return diagonal(grid_label, levelset, weights(x), x, density_ratio());
+}
+
+fn weights(x: vec2<i32>) -> vec4<f32> {
+    return stencil_fractions(levelset, face_fractions(u_fraction, v_fraction, x), x, density_ratio());
+}
+
+fn density_ratio() -> f32 {
+    return constants.air_rho / constants.rho;
+}
+
+fn fluid_p(x: vec2<i32>) -> f32 {
+    return select(0.0, textureLoad(p, x).r, is_fluid(x));
+}
+
+fn fluid_sum_p(x: vec2<i32>) -> f32 {
+    let fractions = weights(x);
+    return dot(fractions, vec4<f32>(fluid_p(left(x)), fluid_p(right(x)), fluid_p(bottom(x)), fluid_p(top(x))));
+}
+
+fn fluid_s(x: vec2<i32>) -> f32 {
+    if (is_fluid(x)) {
+        return cells[index(x)].s;
+    }
+    return 0.0;
+}
+
+fn fluid_sum_s(x: vec2<i32>) -> f32 {
+    let fractions = weights(x);
+    return dot(fractions, vec4<f32>(fluid_s(left(x)), fluid_s(right(x)), fluid_s(bottom(x)), fluid_s(top(x))));
+}
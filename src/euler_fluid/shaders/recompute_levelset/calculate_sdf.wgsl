@@ -8,7 +8,7 @@ fn get_seed(x: vec2<i32>) -> vec2<f32> {
 }
 
 @compute
-@workgroup_size(8, 8, 1)
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
 fn calculate_sdf(
     @builtin(global_invocation_id) invocation_id: vec3<u32>,
 ) {
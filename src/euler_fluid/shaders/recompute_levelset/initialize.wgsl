@@ -9,7 +9,7 @@ fn set_seed(x: vec2<i32>, seed: vec2<f32>) {
 }
 
 @compute
-@workgroup_size(8, 8, 1)
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
 fn initialize(
     @builtin(global_invocation_id) global_id: vec3<u32>
 ) {
@@ -0,0 +1,69 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+#import bevy_fluid::coordinate::{left, right, bottom, top};
+#import bevy_fluid::cut_cell::face_fractions;
+#import bevy_fluid::ghost_fluid::{diagonal, is_unknown, stencil_fractions};
+
+@group(0) @binding(0) var<uniform> constants: SimulationUniform;
+
+@group(1) @binding(1) var p1: texture_storage_2d<r32float, read_write>;
+
+@group(2) @binding(0) var div: texture_storage_2d<r32float, read_write>;
+
+@group(3) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(3) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn red_black_sor_red(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    red_black_sor(vec2<i32>(i32(invocation_id.x), i32(invocation_id.y)), 0);
+}
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn red_black_sor_black(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>
+) {
+    red_black_sor(vec2<i32>(i32(invocation_id.x), i32(invocation_id.y)), 1);
+}
+
+// Updates p1 in place on the cells of the given parity.
+// The neighbors of a cell have the other parity, so the cells of the same parity are independent of each other.
+fn red_black_sor(x: vec2<i32>, parity: i32) {
+    if ((x.x + x.y) % 2 != parity) {
+        return;
+    }
+    let label = textureLoad(grid_label, x).r;
+    if (label == 2) {
+        textureStore(p1, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+        return;
+    }
+
+    let density_ratio = constants.air_rho / constants.rho;
+    let fractions = stencil_fractions(levelset, face_fractions(u_fraction, v_fraction, x), x, density_ratio);
+    let coef = diagonal(grid_label, levelset, fractions, x, density_ratio);
+
+    if (coef == 0.0) {
+        textureStore(p1, x, vec4<f32>(0.0, 0.0, 0.0, 0.0));
+        return;
+    } else {
+        let p_left = fractions.x * pij(left(x), density_ratio);
+        let p_right = fractions.y * pij(right(x), density_ratio);
+        let p_bottom = fractions.z * pij(bottom(x), density_ratio);
+        let p_top = fractions.w * pij(top(x), density_ratio);
+        let div_ij = textureLoad(div, x).r;
+
+        let factor = constants.dx * constants.rho / constants.dt;
+        let p_gauss_seidel = (1.0 / coef) * (p_left + p_right + p_bottom + p_top - factor * div_ij);
+        let p_old = textureLoad(p1, x).r;
+        let p = mix(p_old, p_gauss_seidel, constants.sor_omega);
+        textureStore(p1, x, vec4<f32>(p, 0.0, 0.0, 0.0));
+    }
+}
+
+fn pij(x: vec2<i32>, density_ratio: f32) -> f32 {
+    return select(0.0, textureLoad(p1, x).r, is_unknown(grid_label, x, density_ratio));
+}
@@ -0,0 +1,58 @@
+#import bevy_fluid::interpolation::bilinear;
+
+// Resample velocity and levelset from the previous grid into a reallocated grid.
+// Both grids cover the same simulation domain, so positions are mapped by the ratio of their dimensions.
+// Always bilinear regardless of constants.interpolation: the four bind groups are taken by the two grids,
+// and a one-off resample gains little from a cubic kernel that may overshoot on downsampling.
+@group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
+
+@group(2) @binding(0) var u0_old: texture_storage_2d<r32float, read_write>;
+@group(2) @binding(1) var v0_old: texture_storage_2d<r32float, read_write>;
+
+@group(3) @binding(0) var levelset_old: texture_storage_2d<r32float, read_write>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn resample_velocity(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+) {
+    let x = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+
+    // u is located at (i, j + 0.5) and v at (i + 0.5, j) in units of the grid cell.
+    let dim_u = vec2<i32>(textureDimensions(u0));
+    if (x.x < dim_u.x && x.y < dim_u.y) {
+        let scale = vec2<f32>(textureDimensions(u0_old) - vec2<u32>(1, 0)) / vec2<f32>(dim_u - vec2<i32>(1, 0));
+        let x_old = (vec2<f32>(x) + vec2<f32>(0.0, 0.5)) * scale - vec2<f32>(0.0, 0.5);
+        let u = bilinear(u0_old, x_old);
+        textureStore(u0, x, vec4<f32>(u, 0.0, 0.0, 0.0));
+    }
+
+    let dim_v = vec2<i32>(textureDimensions(v0));
+    if (x.x < dim_v.x && x.y < dim_v.y) {
+        let scale = vec2<f32>(textureDimensions(v0_old) - vec2<u32>(0, 1)) / vec2<f32>(dim_v - vec2<i32>(0, 1));
+        let x_old = (vec2<f32>(x) + vec2<f32>(0.5, 0.0)) * scale - vec2<f32>(0.5, 0.0);
+        let v = bilinear(v0_old, x_old);
+        textureStore(v0, x, vec4<f32>(v, 0.0, 0.0, 0.0));
+    }
+}
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn resample_levelset(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+) {
+    let x = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    let dim = vec2<i32>(textureDimensions(levelset));
+    if (x.x >= dim.x || x.y >= dim.y) {
+        return;
+    }
+
+    // levelset is located at cell centers.
+    let scale = vec2<f32>(textureDimensions(levelset_old)) / vec2<f32>(dim);
+    let x_old = (vec2<f32>(x) + vec2<f32>(0.5)) * scale - vec2<f32>(0.5);
+    let level = bilinear(levelset_old, x_old);
+    textureStore(levelset, x, vec4<f32>(level, 0.0, 0.0, 0.0));
+}
@@ -0,0 +1,47 @@
+#import bevy_fluid::coordinate::{right, top};
+#import bevy_fluid::cut_cell::face_fractions;
+#import bevy_fluid::solid_velocity::solid_velocity;
+
+@group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(1) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
+
+// Bit patterns of the reductions. See max_velocity.wgsl.
+// [1]: max |∇·u| over fluid cells, not divided by dx.
+@group(2) @binding(0) var<storage, read_write> reduction: array<atomic<u32>, 3>;
+
+var<workgroup> workgroup_max: atomic<u32>;
+
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn reduce_residual(
+    @builtin(global_invocation_id) invocation_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&workgroup_max, 0u);
+    }
+    workgroupBarrier();
+
+    let x = vec2<i32>(invocation_id.xy);
+    var residual = 0.0;
+    if (all(x < vec2<i32>(textureDimensions(grid_label))) && textureLoad(grid_label, x).r == 1) {
+        // Same fluxes as divergence.wgsl.
+        let fractions = face_fractions(u_fraction, v_fraction, x);
+        let x_center = vec2<f32>(x);
+        let div = mix(solid_velocity(x_center + vec2<f32>(0.5, 0.0)).x, textureLoad(u0, right(x)).r, fractions.y)
+            - mix(solid_velocity(x_center - vec2<f32>(0.5, 0.0)).x, textureLoad(u0, x).r, fractions.x)
+            + mix(solid_velocity(x_center + vec2<f32>(0.0, 0.5)).y, textureLoad(v0, top(x)).r, fractions.w)
+            - mix(solid_velocity(x_center - vec2<f32>(0.0, 0.5)).y, textureLoad(v0, x).r, fractions.z);
+        residual = abs(div);
+    }
+    atomicMax(&workgroup_max, bitcast<u32>(residual));
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        atomicMax(&reduction[1], atomicLoad(&workgroup_max));
+    }
+}
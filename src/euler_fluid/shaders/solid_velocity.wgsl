@@ -0,0 +1,21 @@
+#define_import_path bevy_fluid::solid_velocity
+
+#import bevy_fluid::cut_cell::{Circle, circle_sdf};
+
+// Shared by the passes computing the flux through the faces, which bind the circles on the same slot.
+// Storage buffers cannot be passed to functions, so the binding is declared here instead of in cut_cell.wgsl.
+@group(3) @binding(0) var<storage, read> circles: array<Circle>;
+
+// Velocity of the closest circle to the face at x_face, or 0 for the walls of the domain.
+fn solid_velocity(x_face: vec2<f32>) -> vec2<f32> {
+    var sdf = 1.0;
+    var velocity = vec2<f32>(0.0, 0.0);
+    for (var i = 0u; i < arrayLength(&circles); i++) {
+        let sdf_i = circle_sdf(circles[i], x_face);
+        if (sdf_i < sdf) {
+            sdf = sdf_i;
+            velocity = circles[i].velocity;
+        }
+    }
+    return velocity;
+}
@@ -1,4 +1,5 @@
 #import bevy_fluid::fluid_uniform::SimulationUniform;
+#import bevy_fluid::ghost_fluid::{face_density, face_pressures};
 
 @group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
 @group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
@@ -9,12 +10,17 @@
 
 @group(2) @binding(1) var p1: texture_storage_2d<r32float, read_write>;
 
+@group(3) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
 @group(3) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(3) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
 
 @compute
-@workgroup_size(1, 64, 1)
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
 fn solve_velocity(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    // In two-phase flow, the faces are accelerated by the pressure according to their densities.
     let factor = constants.dt / (constants.dx * constants.rho);
+    let density_ratio = constants.air_rho / constants.rho;
 
     let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
 
@@ -26,14 +32,23 @@ fn solve_velocity(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
     } else if (grid_label_u1 == 2) {
         let u_solid = textureLoad(u1, x_u).r;
         textureStore(u0, x_u, vec4<f32>(u_solid, 0.0, 0.0, 0.0));
+    } else if (textureLoad(u_fraction, x_u).r == 0.0) {
+        // Closed by the obstacles. The velocity does not contribute to the divergence.
+        textureStore(u0, x_u, textureLoad(u1, x_u));
     } else {
         let p1_u = textureLoad(p1, x_u).r;
         var p0_u = 0.0;
         if x_u.x != 0 {
             p0_u = textureLoad(p1, x_u - vec2<i32>(1, 0)).r;
         }
+        let p_u = face_pressures(grid_label, levelset, x_u - vec2<i32>(1, 0), x_u, p0_u, p1_u, density_ratio);
+        let density_u = face_density(
+            textureLoad(levelset, x_u - vec2<i32>(1, 0)).r,
+            textureLoad(levelset, x_u).r,
+            density_ratio,
+        );
         let u = textureLoad(u1, x_u);
-        let du = vec4<f32>(factor * (p1_u - p0_u), 0.0, 0.0, 0.0);
+        let du = vec4<f32>(factor / density_u * (p_u.y - p_u.x), 0.0, 0.0, 0.0);
         textureStore(u0, x_u, u - du);
     }
 
@@ -47,14 +62,22 @@ fn solve_velocity(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
     } else if (grid_label_v1 == 2) {
         let v_solid = textureLoad(v1, x_v).r;
         textureStore(v0, x_v, vec4<f32>(v_solid, 0.0, 0.0, 0.0));
+    } else if (textureLoad(v_fraction, x_v).r == 0.0) {
+        textureStore(v0, x_v, textureLoad(v1, x_v));
     } else {
         let p1_v = textureLoad(p1, x_v).r;
         var p0_v = 0.0;
         if x_v.y != 0 {
             p0_v = textureLoad(p1, x_v - vec2<i32>(0, 1)).r;
         }
+        let p_v = face_pressures(grid_label, levelset, x_v - vec2<i32>(0, 1), x_v, p0_v, p1_v, density_ratio);
+        let density_v = face_density(
+            textureLoad(levelset, x_v - vec2<i32>(0, 1)).r,
+            textureLoad(levelset, x_v).r,
+            density_ratio,
+        );
         let v = textureLoad(v1, x_v);
-        let dv = vec4<f32>(factor * (p1_v - p0_v), 0.0, 0.0, 0.0);
+        let dv = vec4<f32>(factor / density_v * (p_v.y - p_v.x), 0.0, 0.0, 0.0);
         textureStore(v0, x_v, v - dv);
     }
 }
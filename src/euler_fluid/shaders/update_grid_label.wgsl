@@ -1,19 +1,18 @@
-struct Circle {
-    radius: f32,
-    center: vec2<f32>,
-    velocity: vec2<f32>,
-}
+#import bevy_fluid::cut_cell::{Circle, circle_sdf, open_fraction};
+
 @group(0) @binding(0) var u0: texture_storage_2d<r32float, read_write>;
 @group(0) @binding(1) var v0: texture_storage_2d<r32float, read_write>;
 
 @group(1) @binding(0) var levelset: texture_storage_2d<r32float, read_write>;
 @group(1) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+@group(1) @binding(2) var u_fraction: texture_storage_2d<r32float, read_write>;
+@group(1) @binding(3) var v_fraction: texture_storage_2d<r32float, read_write>;
 
 @group(2) @binding(0) var<storage, read> circles: array<Circle>;
 
 
 @compute
-@workgroup_size(8, 8, 1)
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
 fn update_grid_label(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let x = vec2<i32>(i32(global_id.x), i32(global_id.y));
     let dim_grid = textureDimensions(grid_label);
@@ -41,11 +40,16 @@ fn update_grid_label(@builtin(global_invocation_id) global_id: vec3<u32>) {
             break;
         }
         let circle = circles[i];
-        let dx = f32(x.x) - circle.center.x;
-        let dy = f32(x.y) - circle.center.y;
-        let distance = sqrt(dx * dx + dy * dy);
+        // The cell is solid only if the circle covers all of its corners.
+        // Partially covered cells are handled by the open fractions of their faces.
+        let x_center = vec2<f32>(x);
+        var covered = true;
+        for (var corner = 0; corner < 4; corner++) {
+            let offset = vec2<f32>(f32(corner % 2), f32(corner / 2)) - 0.5;
+            covered = covered && circle_sdf(circle, x_center + offset) < 0.0;
+        }
 
-        if distance < circle.radius {
+        if covered {
             label = 2u;
             u = circle.velocity.x;
             v = circle.velocity.y;
@@ -61,4 +65,50 @@ fn update_grid_label(@builtin(global_invocation_id) global_id: vec3<u32>) {
         textureStore(u0, x, vec4<f32>(u, 0.0, 0.0, 0.0));
         textureStore(v0, x, vec4<f32>(v, 0.0, 0.0, 0.0));
     }
-}
\ No newline at end of file
+}
+
+// Computes the fraction of each face not covered by the obstacles
+// from the signed distances at the two ends of the face.
+// Faces next to solid cells are closed.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn update_face_fraction(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    var fraction_u = 0.0;
+    if (!is_solid(x_u - vec2<i32>(1, 0)) && !is_solid(x_u)) {
+        let x_face = vec2<f32>(x_u) - vec2<f32>(0.5, 0.0);
+        fraction_u = open_fraction(
+            obstacle_sdf(x_face - vec2<f32>(0.0, 0.5)),
+            obstacle_sdf(x_face + vec2<f32>(0.0, 0.5)),
+        );
+    }
+    textureStore(u_fraction, x_u, vec4<f32>(fraction_u, 0.0, 0.0, 0.0));
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    var fraction_v = 0.0;
+    if (!is_solid(x_v - vec2<i32>(0, 1)) && !is_solid(x_v)) {
+        let x_face = vec2<f32>(x_v) - vec2<f32>(0.0, 0.5);
+        fraction_v = open_fraction(
+            obstacle_sdf(x_face - vec2<f32>(0.5, 0.0)),
+            obstacle_sdf(x_face + vec2<f32>(0.5, 0.0)),
+        );
+    }
+    textureStore(v_fraction, x_v, vec4<f32>(fraction_v, 0.0, 0.0, 0.0));
+}
+
+// Cells out of the grid are solid.
+fn is_solid(x: vec2<i32>) -> bool {
+    if (any(x < vec2<i32>(0)) || any(x >= vec2<i32>(textureDimensions(grid_label)))) {
+        return true;
+    }
+    return textureLoad(grid_label, x).r == 2u;
+}
+
+// Signed distance to the union of the circles. Positive everywhere without circles.
+fn obstacle_sdf(x: vec2<f32>) -> f32 {
+    var sdf = 1.0e9;
+    for (var i = 0u; i < arrayLength(&circles); i++) {
+        sdf = min(sdf, circle_sdf(circles[i], x));
+    }
+    return sdf;
+}
@@ -0,0 +1,158 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+
+// Viscous diffusion of the velocity, du/dt = viscosity * laplacian(u), on each face grid
+// with alpha = viscosity * dt / dx^2.
+// Faces touching a solid cell keep their velocity, and solid cells act as no-slip walls:
+// a neighbor face on a solid cell along the face normal is the wall itself with zero velocity,
+// and a neighbor face across the wall is the mirror image -u.
+// Neighbor faces in the air mirror u so that the free surface exerts no stress.
+
+@group(0) @binding(2) var u1: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(3) var v1: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+
+@group(2) @binding(0) var<uniform> constants: SimulationUniform;
+
+@group(3) @binding(0) var u_old: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(1) var v_old: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(2) var u_tmp: texture_storage_2d<r32float, read_write>;
+@group(3) @binding(3) var v_tmp: texture_storage_2d<r32float, read_write>;
+
+// u_old = u1. The velocity before diffusion is the right hand side of the implicit solve.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn viscosity_copy(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u)) {
+        textureStore(u_old, x_u, textureLoad(u1, x_u));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v)) {
+        textureStore(v_old, x_v, textureLoad(v1, x_v));
+    }
+}
+
+// u1 = u_old + alpha * laplacian(u_old). Stable only while alpha is below 0.25.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn viscosity_explicit(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let alpha = diffusion_number();
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u) && is_diffused_face(x_u, vec2<i32>(1, 0))) {
+        let u = textureLoad(u_old, x_u).r;
+        let terms = neighbor_terms(u_old, x_u, vec2<i32>(1, 0));
+        let value = u + alpha * (terms.x - (4.0 + terms.y) * u);
+        textureStore(u1, x_u, vec4<f32>(value, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v) && is_diffused_face(x_v, vec2<i32>(0, 1))) {
+        let v = textureLoad(v_old, x_v).r;
+        let terms = neighbor_terms(v_old, x_v, vec2<i32>(0, 1));
+        let value = v + alpha * (terms.x - (4.0 + terms.y) * v);
+        textureStore(v1, x_v, vec4<f32>(value, 0.0, 0.0, 0.0));
+    }
+}
+
+// u_tmp = Jacobi iteration of (1 - alpha * laplacian) u = u_old from u1.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn viscosity_jacobi(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u)) {
+        let value = jacobi(u1, u_old, x_u, vec2<i32>(1, 0));
+        textureStore(u_tmp, x_u, vec4<f32>(value, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v)) {
+        let value = jacobi(v1, v_old, x_v, vec2<i32>(0, 1));
+        textureStore(v_tmp, x_v, vec4<f32>(value, 0.0, 0.0, 0.0));
+    }
+}
+
+// u1 = Jacobi iteration of (1 - alpha * laplacian) u = u_old from u_tmp.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn viscosity_jacobi_reverse(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (in_texture(u1, x_u)) {
+        let value = jacobi(u_tmp, u_old, x_u, vec2<i32>(1, 0));
+        textureStore(u1, x_u, vec4<f32>(value, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (in_texture(v1, x_v)) {
+        let value = jacobi(v_tmp, v_old, x_v, vec2<i32>(0, 1));
+        textureStore(v1, x_v, vec4<f32>(value, 0.0, 0.0, 0.0));
+    }
+}
+
+fn jacobi(
+    src: texture_storage_2d<r32float, read_write>,
+    rhs: texture_storage_2d<r32float, read_write>,
+    x: vec2<i32>,
+    direction: vec2<i32>,
+) -> f32 {
+    if (!is_diffused_face(x, direction)) {
+        return textureLoad(src, x).r;
+    }
+    let alpha = diffusion_number();
+    let terms = neighbor_terms(src, x, direction);
+    return (textureLoad(rhs, x).r + alpha * terms.x) / (1.0 + alpha * (4.0 + terms.y));
+}
+
+fn diffusion_number() -> f32 {
+    return constants.viscosity * constants.dt / (constants.dx * constants.dx);
+}
+
+// Sum of the neighbor faces which carry their own velocity, and the number of neighbor faces
+// which mirror -u across a wall minus the number of neighbor faces which mirror u in the air.
+fn neighbor_terms(
+    grid: texture_storage_2d<r32float, read_write>,
+    x: vec2<i32>,
+    direction: vec2<i32>,
+) -> vec2<f32> {
+    let tangent = vec2<i32>(direction.y, direction.x);
+    var terms = vec2<f32>(0.0);
+    for (var k = 0; k < 4; k++) {
+        let side = select(-1, 1, k % 2 == 0);
+        let is_normal = k < 2;
+        let offset = select(tangent, direction, is_normal) * side;
+        let x_neighbor = x + offset;
+        let label_minus = label(x_neighbor - direction);
+        let label_plus = label(x_neighbor);
+        if (label_minus == 2u || label_plus == 2u) {
+            // Along the normal the neighbor face is the wall, whose velocity is zero.
+            if (!is_normal) {
+                terms.y += 1.0;
+            }
+        } else if (label_minus == 0u && label_plus == 0u) {
+            terms.y -= 1.0;
+        } else {
+            terms.x += textureLoad(grid, x_neighbor).r;
+        }
+    }
+    return terms;
+}
+
+// Whether the face x between the cells x - direction and x touches the fluid and no solid.
+fn is_diffused_face(x: vec2<i32>, direction: vec2<i32>) -> bool {
+    let label_minus = label(x - direction);
+    let label_plus = label(x);
+    return label_minus != 2u && label_plus != 2u && (label_minus == 1u || label_plus == 1u);
+}
+
+fn in_texture(grid: texture_storage_2d<r32float, read_write>, x: vec2<i32>) -> bool {
+    return all(x >= vec2<i32>(0)) && all(x < vec2<i32>(textureDimensions(grid)));
+}
+
+// Cells out of the grid are solid.
+fn label(x: vec2<i32>) -> u32 {
+    if (any(x < vec2<i32>(0)) || any(x >= vec2<i32>(textureDimensions(grid_label)))) {
+        return 2u;
+    }
+    return textureLoad(grid_label, x).r;
+}
@@ -0,0 +1,89 @@
+#import bevy_fluid::fluid_uniform::SimulationUniform;
+
+// Vorticity confinement.
+// The curl of the advected velocity is computed on the cell centers, and the force
+// epsilon * (N x omega) with N = grad|omega| / |grad|omega|| is added to the velocity,
+// which pushes the velocity around the local maxima of the vorticity to restore the swirls damped by advection.
+// Lengths are in grid units as in advection.
+
+@group(0) @binding(2) var u1: texture_storage_2d<r32float, read_write>;
+@group(0) @binding(3) var v1: texture_storage_2d<r32float, read_write>;
+
+@group(1) @binding(1) var grid_label: texture_storage_2d<r32uint, read_write>;
+
+@group(2) @binding(0) var<uniform> constants: SimulationUniform;
+
+@group(3) @binding(0) var vorticity_texture: texture_storage_2d<r32float, read_write>;
+
+// vorticity_texture = curl of (u1, v1) on the fluid cells, 0 elsewhere.
+@compute
+@workgroup_size(#{WORKGROUP_SIZE}, #{WORKGROUP_SIZE}, 1)
+fn vorticity(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x = vec2<i32>(invocation_id.xy);
+    let dim = vec2<i32>(textureDimensions(grid_label));
+    if (any(x >= dim)) {
+        return;
+    }
+
+    var omega = 0.0;
+    if (textureLoad(grid_label, x).r == 1u) {
+        let dv_dx = center_v(x + vec2<i32>(1, 0)) - center_v(x - vec2<i32>(1, 0));
+        let du_dy = center_u(x + vec2<i32>(0, 1)) - center_u(x - vec2<i32>(0, 1));
+        omega = 0.5 * (dv_dx - du_dy);
+    }
+    textureStore(vorticity_texture, x, vec4<f32>(omega, 0.0, 0.0, 0.0));
+}
+
+// (u1, v1) += dt * confinement force, averaged from the two cells of each face between the fluid cells.
+@compute
+@workgroup_size(1, #{FACE_WORKGROUP_SIZE}, 1)
+fn vorticity_confinement(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let x_u = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (is_fluid_face(x_u, vec2<i32>(1, 0))) {
+        let force = 0.5 * (confinement_force(x_u - vec2<i32>(1, 0)).x + confinement_force(x_u).x);
+        let u = textureLoad(u1, x_u).r + constants.dt * force;
+        textureStore(u1, x_u, vec4<f32>(u, 0.0, 0.0, 0.0));
+    }
+
+    let x_v = vec2<i32>(x_u.y, x_u.x);
+    if (is_fluid_face(x_v, vec2<i32>(0, 1))) {
+        let force = 0.5 * (confinement_force(x_v - vec2<i32>(0, 1)).y + confinement_force(x_v).y);
+        let v = textureLoad(v1, x_v).r + constants.dt * force;
+        textureStore(v1, x_v, vec4<f32>(v, 0.0, 0.0, 0.0));
+    }
+}
+
+fn confinement_force(x: vec2<i32>) -> vec2<f32> {
+    let gradient = 0.5 * vec2<f32>(
+        abs(vorticity_at(x + vec2<i32>(1, 0))) - abs(vorticity_at(x - vec2<i32>(1, 0))),
+        abs(vorticity_at(x + vec2<i32>(0, 1))) - abs(vorticity_at(x - vec2<i32>(0, 1))),
+    );
+    let n = gradient / (length(gradient) + 1.0e-5);
+    let omega = vorticity_at(x);
+    return constants.vorticity_confinement * vec2<f32>(n.y * omega, -n.x * omega);
+}
+
+// Whether the face x between the cells x - direction and x lies in the fluid.
+fn is_fluid_face(x: vec2<i32>, direction: vec2<i32>) -> bool {
+    let dim = vec2<i32>(textureDimensions(grid_label));
+    let x_minus = x - direction;
+    if (any(x_minus < vec2<i32>(0)) || any(x >= dim)) {
+        return false;
+    }
+    return textureLoad(grid_label, x_minus).r == 1u && textureLoad(grid_label, x).r == 1u;
+}
+
+fn vorticity_at(x: vec2<i32>) -> f32 {
+    let dim = vec2<i32>(textureDimensions(vorticity_texture));
+    return textureLoad(vorticity_texture, clamp(x, vec2<i32>(0), dim - 1)).r;
+}
+
+fn center_u(x: vec2<i32>) -> f32 {
+    let x_clamped = clamp(x, vec2<i32>(0), vec2<i32>(textureDimensions(grid_label)) - 1);
+    return 0.5 * (textureLoad(u1, x_clamped).r + textureLoad(u1, x_clamped + vec2<i32>(1, 0)).r);
+}
+
+fn center_v(x: vec2<i32>) -> f32 {
+    let x_clamped = clamp(x, vec2<i32>(0), vec2<i32>(textureDimensions(grid_label)) - 1);
+    return 0.5 * (textureLoad(v1, x_clamped).r + textureLoad(v1, x_clamped + vec2<i32>(0, 1)).r);
+}
@@ -13,9 +13,7 @@ pub trait ImageForCS {
 
 impl ImageForCS for Image {
     fn new_texture_storage(size: (u32, u32), format: TextureFormat) -> Self {
-        let pixel_size = format.pixel_size();
-        let mut zeros = Vec::new();
-        zeros.resize(pixel_size, 0u8);
+        let zeros = vec![0u8; format.pixel_size()];
 
         let mut image = Image::new_fill(
             Extent3d {
@@ -40,7 +38,7 @@ pub trait NewTexture {
     fn new_texture_storage(&mut self, size: (u32, u32), format: TextureFormat) -> Handle<Image>;
 }
 
-impl<'a> NewTexture for ResMut<'a, Assets<Image>> {
+impl NewTexture for Assets<Image> {
     fn new_texture_storage(&mut self, size: (u32, u32), format: TextureFormat) -> Handle<Image> {
         let u0 = Image::new_texture_storage(size, format);
         self.add(u0)